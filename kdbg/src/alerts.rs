@@ -0,0 +1,626 @@
+use crate::config::{AlertCondition, AlertRule};
+use crate::kubectl::{pod_status, restart_count};
+use crate::utils::parse_duration;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Give up on a webhook after this long
+const WEBHOOK_TIMEOUT_SECS: &str = "10";
+
+/// An alert rule with its durations parsed
+struct Rule {
+    name: String,
+    rule: AlertRule,
+    hold: Duration,
+    cooldown: Duration,
+}
+
+/// Where one rule stands for one pod
+#[derive(Default)]
+struct RuleState {
+    /// When the condition was first seen holding (cleared when it stops)
+    since: Option<Instant>,
+    /// Identity of the occurrence already alerted on, so one crash loop or
+    /// one OOM kill alerts once rather than on every update
+    fired: Option<String>,
+    last_fired: Option<Instant>,
+}
+
+/// Evaluates alert rules against pods and runs their hooks in the background
+pub struct Alerter {
+    rules: Vec<Rule>,
+    /// Keyed by (rule index, "namespace/name")
+    states: HashMap<(usize, String), RuleState>,
+    results_tx: Sender<String>,
+    results_rx: Receiver<String>,
+}
+
+impl AlertCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertCondition::CrashLoop => "crash_loop",
+            AlertCondition::Restarts => "restarts",
+            AlertCondition::Pending => "pending",
+            AlertCondition::OomKilled => "oom_killed",
+        }
+    }
+}
+
+impl Alerter {
+    /// Validate the configured rules
+    pub fn new(rules: &[AlertRule]) -> Result<Self> {
+        let duration = |text: &str, rule: &str| {
+            parse_duration(text)
+                .map(Duration::from_secs)
+                .with_context(|| format!("Alert '{}': invalid duration '{}'", rule, text))
+        };
+
+        let mut compiled = Vec::new();
+        for rule in rules {
+            let name = rule
+                .name
+                .clone()
+                .unwrap_or_else(|| rule.when.as_str().to_string());
+
+            if rule.command.is_none() && rule.webhook.is_none() {
+                anyhow::bail!("Alert '{}' needs a command or a webhook", name);
+            }
+            if rule.when == AlertCondition::Restarts && rule.threshold.is_none() {
+                anyhow::bail!("Alert '{}' needs a threshold for 'restarts'", name);
+            }
+            if rule.when == AlertCondition::Pending && rule.for_duration.is_none() {
+                anyhow::bail!("Alert '{}' needs 'for' (e.g. \"5m\") for 'pending'", name);
+            }
+
+            compiled.push(Rule {
+                hold: match &rule.for_duration {
+                    Some(text) => duration(text, &name)?,
+                    None => Duration::ZERO,
+                },
+                cooldown: duration(&rule.cooldown, &name)?,
+                name,
+                rule: rule.clone(),
+            });
+        }
+
+        let (results_tx, results_rx) = mpsc::channel();
+        Ok(Alerter {
+            rules: compiled,
+            states: HashMap::new(),
+            results_tx,
+            results_rx,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Check every rule against the current state of a pod, firing those
+    /// that are due. Returns a description of each alert fired.
+    pub fn check(&mut self, key: &str, pod: &Value) -> Vec<String> {
+        let mut fired = Vec::new();
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            if !applies_to(&rule.rule, pod) {
+                continue;
+            }
+
+            let state = self.states.entry((index, key.to_string())).or_default();
+            let Some((occurrence, started)) = matches(&rule.rule, pod) else {
+                state.since = None;
+                state.fired = None;
+                continue;
+            };
+
+            // Debounce: the condition has to hold for `for`. For Pending the
+            // pod's age counts, even from before the watch started.
+            let since = *state.since.get_or_insert_with(Instant::now);
+            let held = started
+                .and_then(|t| (Utc::now() - t).to_std().ok())
+                .map_or(since.elapsed(), |age| age.max(since.elapsed()));
+            if held < rule.hold || state.fired.as_ref() == Some(&occurrence) {
+                continue;
+            }
+            if state
+                .last_fired
+                .is_some_and(|t| t.elapsed() < rule.cooldown)
+            {
+                continue;
+            }
+
+            state.fired = Some(occurrence);
+            state.last_fired = Some(Instant::now());
+
+            let description = describe(rule, pod);
+            run_hooks(
+                &rule.rule,
+                alert_payload(rule, pod),
+                self.results_tx.clone(),
+            );
+            fired.push(format!("{}: {}", rule.name, description));
+        }
+
+        fired
+    }
+
+    /// Reset the state kept for a deleted pod. The cooldown is kept, so a
+    /// pod recreated under the same name (e.g. by a StatefulSet) doesn't
+    /// alert again straight away.
+    pub fn forget(&mut self, key: &str) {
+        self.states.retain(|(_, pod), state| {
+            if pod != key {
+                return true;
+            }
+            state.since = None;
+            state.fired = None;
+            state.last_fired.is_some()
+        });
+    }
+
+    /// Outcomes of hooks that finished since the last call
+    pub fn results(&self) -> Vec<String> {
+        self.results_rx.try_iter().collect()
+    }
+}
+
+fn applies_to(rule: &AlertRule, pod: &Value) -> bool {
+    let ns = pod["metadata"]["namespace"].as_str().unwrap_or("default");
+    let name = pod["metadata"]["name"].as_str().unwrap_or("");
+
+    let namespace_ok = match &rule.namespace {
+        Some(wanted) => wanted == ns,
+        None => true,
+    };
+    let pod_ok = match &rule.pod {
+        Some(wanted) => name.contains(wanted.as_str()),
+        None => true,
+    };
+
+    namespace_ok && pod_ok
+}
+
+/// If the pod is in the rule's condition: an identifier of this occurrence
+/// and, when known, when it started
+fn matches(rule: &AlertRule, pod: &Value) -> Option<(String, Option<DateTime<Utc>>)> {
+    let statuses: Vec<&Value> = ["initContainerStatuses", "containerStatuses"]
+        .iter()
+        .filter_map(|key| pod["status"][key].as_array())
+        .flatten()
+        .collect();
+
+    match rule.when {
+        AlertCondition::CrashLoop => statuses
+            .iter()
+            .any(|c| c["state"]["waiting"]["reason"].as_str() == Some("CrashLoopBackOff"))
+            .then(|| ("crash_loop".to_string(), None)),
+        AlertCondition::Restarts => {
+            let restarts = restart_count(pod);
+            (restarts > rule.threshold.unwrap_or(0)).then(|| (restarts.to_string(), None))
+        }
+        AlertCondition::Pending => (pod["status"]["phase"].as_str() == Some("Pending")
+            && pod["metadata"]["deletionTimestamp"].is_null())
+        .then(|| {
+            let created = pod["metadata"]["creationTimestamp"]
+                .as_str()
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.with_timezone(&Utc));
+            ("pending".to_string(), created)
+        }),
+        AlertCondition::OomKilled => statuses
+            .iter()
+            .flat_map(|c| {
+                [&c["state"]["terminated"], &c["lastState"]["terminated"]]
+                    .into_iter()
+                    .filter(|t| t["reason"].as_str() == Some("OOMKilled"))
+                    .map(move |t| {
+                        format!(
+                            "{}@{}",
+                            c["name"].as_str().unwrap_or("?"),
+                            t["finishedAt"].as_str().unwrap_or("")
+                        )
+                    })
+            })
+            .max_by(|a, b| a.split('@').nth(1).cmp(&b.split('@').nth(1)))
+            .map(|occurrence| (occurrence, None)),
+    }
+}
+
+/// One-line summary of why the rule fired
+fn describe(rule: &Rule, pod: &Value) -> String {
+    let key = format!(
+        "{}/{}",
+        pod["metadata"]["namespace"].as_str().unwrap_or("default"),
+        pod["metadata"]["name"].as_str().unwrap_or("unknown")
+    );
+    match rule.rule.when {
+        AlertCondition::CrashLoop => format!("{} is in CrashLoopBackOff", key),
+        AlertCondition::Restarts => format!(
+            "{} has restarted {} times (threshold {})",
+            key,
+            restart_count(pod),
+            rule.rule.threshold.unwrap_or(0)
+        ),
+        AlertCondition::Pending => format!(
+            "{} Pending for over {}s ({})",
+            key,
+            rule.hold.as_secs(),
+            pod_status(pod)
+        ),
+        AlertCondition::OomKilled => format!("{} was OOMKilled", key),
+    }
+}
+
+fn alert_payload(rule: &Rule, pod: &Value) -> Value {
+    json!({
+        "alert": rule.name,
+        "condition": rule.rule.when.as_str(),
+        "pod": pod["metadata"]["name"],
+        "namespace": pod["metadata"]["namespace"],
+        "node": pod["spec"]["nodeName"],
+        "status": pod_status(pod),
+        "restarts": restart_count(pod),
+        "message": describe(rule, pod),
+        "time": Utc::now().to_rfc3339(),
+    })
+}
+
+/// Run the rule's command and webhook on a background thread, reporting
+/// failures (and webhook deliveries) through `results`
+fn run_hooks(rule: &AlertRule, payload: Value, results: Sender<String>) {
+    let command = rule.command.clone();
+    let webhook = rule.webhook.clone();
+    let name = payload["alert"].as_str().unwrap_or("alert").to_string();
+
+    thread::spawn(move || {
+        if let Some(command) = command {
+            let env = |key: &str| match &payload[key] {
+                Value::String(s) => s.clone(),
+                Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            let status = Command::new("sh")
+                .args(["-c", &command])
+                .env("KDBG_ALERT", env("alert"))
+                .env("KDBG_CONDITION", env("condition"))
+                .env("KDBG_POD", env("pod"))
+                .env("KDBG_NAMESPACE", env("namespace"))
+                .env("KDBG_NODE", env("node"))
+                .env("KDBG_STATUS", env("status"))
+                .env("KDBG_RESTARTS", env("restarts"))
+                .env("KDBG_MESSAGE", env("message"))
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+            match status {
+                Ok(status) if status.success() => {}
+                Ok(status) => {
+                    let _ = results.send(format!("{}: command failed ({})", name, status));
+                }
+                Err(err) => {
+                    let _ = results.send(format!("{}: command failed ({})", name, err));
+                }
+            }
+        }
+
+        if let Some(url) = webhook {
+            let outcome = post_json(&url, &payload);
+            let _ = results.send(match outcome {
+                Ok(()) => format!("{}: webhook delivered to {}", name, url),
+                Err(err) => format!("{}: webhook to {} failed ({})", name, url, err),
+            });
+        }
+    });
+}
+
+/// POST a JSON body with curl
+fn post_json(url: &str, payload: &Value) -> Result<()> {
+    let mut child = Command::new("curl")
+        .args([
+            "-sS",
+            "--fail",
+            "--max-time",
+            WEBHOOK_TIMEOUT_SECS,
+            "-X",
+            "POST",
+            "-H",
+            "Content-Type: application/json",
+            "--data-binary",
+            "@-",
+            url,
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("curl not found")?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(payload.to_string().as_bytes())?;
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        anyhow::bail!("curl exited with {}", status.code().unwrap_or(-1));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+
+    /// A local HTTP listener answering 200 to every request and handing each
+    /// request body to the returned channel
+    fn webhook_listener() -> (String, Receiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some((key, value)) = line.split_once(':') {
+                        if key.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap_or(0);
+                        }
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+                let _ = tx.send(serde_json::from_slice(&body).unwrap());
+            }
+        });
+
+        (url, rx)
+    }
+
+    fn alerter(rule: Value) -> Alerter {
+        let rule: AlertRule = serde_json::from_value(rule).unwrap();
+        Alerter::new(&[rule]).unwrap()
+    }
+
+    fn pod(name: &str, status: Value) -> Value {
+        json!({
+            "metadata": {
+                "name": name,
+                "namespace": "prod",
+                "creationTimestamp": Utc::now().to_rfc3339(),
+            },
+            "spec": { "nodeName": "node-1" },
+            "status": status,
+        })
+    }
+
+    fn crash_looping(restarts: u64) -> Value {
+        pod(
+            "api-1",
+            json!({
+                "phase": "Running",
+                "containerStatuses": [{
+                    "name": "app",
+                    "restartCount": restarts,
+                    "state": { "waiting": { "reason": "CrashLoopBackOff" } },
+                }],
+            }),
+        )
+    }
+
+    fn received(rx: &Receiver<Value>) -> Value {
+        rx.recv_timeout(Duration::from_secs(10))
+            .expect("webhook was not called")
+    }
+
+    fn assert_quiet(rx: &Receiver<Value>) {
+        if let Ok(body) = rx.recv_timeout(Duration::from_millis(500)) {
+            panic!("unexpected webhook call: {}", body);
+        }
+    }
+
+    #[test]
+    fn crash_loop_posts_alert() {
+        let (url, rx) = webhook_listener();
+        let mut alerts = alerter(json!({ "when": "crash_loop", "webhook": url }));
+
+        let fired = alerts.check("prod/api-1", &crash_looping(3));
+        assert_eq!(fired, ["crash_loop: prod/api-1 is in CrashLoopBackOff"]);
+
+        let body = received(&rx);
+        assert_eq!(body["alert"], "crash_loop");
+        assert_eq!(body["condition"], "crash_loop");
+        assert_eq!(body["pod"], "api-1");
+        assert_eq!(body["namespace"], "prod");
+        assert_eq!(body["node"], "node-1");
+        assert_eq!(body["status"], "CrashLoopBackOff");
+        assert_eq!(body["restarts"], 3);
+        assert_eq!(body["message"], "prod/api-1 is in CrashLoopBackOff");
+
+        // Still the same crash loop: no second alert
+        assert!(alerts.check("prod/api-1", &crash_looping(4)).is_empty());
+        assert_quiet(&rx);
+    }
+
+    #[test]
+    fn restarts_over_threshold_posts_alert() {
+        let (url, rx) = webhook_listener();
+        let mut alerts = alerter(json!({
+            "name": "too-many-restarts",
+            "when": "restarts",
+            "threshold": 2,
+            "cooldown": "0s",
+            "webhook": url,
+        }));
+
+        assert!(alerts.check("prod/api-1", &crash_looping(2)).is_empty());
+        assert_eq!(alerts.check("prod/api-1", &crash_looping(3)).len(), 1);
+        let body = received(&rx);
+        assert_eq!(body["alert"], "too-many-restarts");
+        assert_eq!(body["restarts"], 3);
+        assert_eq!(
+            body["message"],
+            "prod/api-1 has restarted 3 times (threshold 2)"
+        );
+
+        // Each further restart is a new occurrence
+        assert!(alerts.check("prod/api-1", &crash_looping(3)).is_empty());
+        assert_eq!(alerts.check("prod/api-1", &crash_looping(4)).len(), 1);
+        assert_eq!(received(&rx)["restarts"], 4);
+    }
+
+    #[test]
+    fn pending_is_debounced_by_for() {
+        let (url, rx) = webhook_listener();
+        let mut alerts = alerter(json!({ "when": "pending", "for": "10m", "webhook": url }));
+
+        let mut fresh = pod("db-0", json!({ "phase": "Pending" }));
+        assert!(alerts.check("prod/db-0", &fresh).is_empty());
+        assert_quiet(&rx);
+
+        // The pod's age counts towards `for`, even from before the watch
+        fresh["metadata"]["creationTimestamp"] =
+            json!((Utc::now() - chrono::Duration::minutes(11)).to_rfc3339());
+        assert_eq!(
+            alerts.check("prod/db-0", &fresh),
+            ["pending: prod/db-0 Pending for over 600s (Pending)"]
+        );
+        let body = received(&rx);
+        assert_eq!(body["condition"], "pending");
+        assert_eq!(body["pod"], "db-0");
+    }
+
+    #[test]
+    fn for_requires_the_condition_to_hold() {
+        let (url, rx) = webhook_listener();
+        let mut alerts = alerter(json!({ "when": "crash_loop", "for": "1s", "webhook": url }));
+
+        assert!(alerts.check("prod/api-1", &crash_looping(1)).is_empty());
+        thread::sleep(Duration::from_millis(1100));
+        assert_eq!(alerts.check("prod/api-1", &crash_looping(1)).len(), 1);
+        received(&rx);
+    }
+
+    #[test]
+    fn oom_killed_posts_alert_once_per_kill() {
+        let (url, rx) = webhook_listener();
+        let mut alerts = alerter(json!({ "when": "oom_killed", "cooldown": "0s", "webhook": url }));
+
+        let oom = |finished: &str| {
+            pod(
+                "worker-1",
+                json!({
+                    "phase": "Running",
+                    "containerStatuses": [{
+                        "name": "worker",
+                        "restartCount": 1,
+                        "state": { "running": {} },
+                        "lastState": { "terminated": {
+                            "reason": "OOMKilled",
+                            "exitCode": 137,
+                            "finishedAt": finished,
+                        } },
+                    }],
+                }),
+            )
+        };
+
+        let fired = alerts.check("prod/worker-1", &oom("2026-01-01T00:00:00Z"));
+        assert_eq!(fired, ["oom_killed: prod/worker-1 was OOMKilled"]);
+        assert_eq!(received(&rx)["condition"], "oom_killed");
+
+        assert!(alerts
+            .check("prod/worker-1", &oom("2026-01-01T00:00:00Z"))
+            .is_empty());
+        assert_eq!(
+            alerts
+                .check("prod/worker-1", &oom("2026-01-01T00:05:00Z"))
+                .len(),
+            1
+        );
+        received(&rx);
+    }
+
+    #[test]
+    fn cooldown_suppresses_new_occurrences() {
+        let (url, rx) = webhook_listener();
+        let mut alerts = alerter(json!({
+            "when": "restarts",
+            "threshold": 1,
+            "cooldown": "1h",
+            "webhook": url,
+        }));
+
+        assert_eq!(alerts.check("prod/api-1", &crash_looping(2)).len(), 1);
+        received(&rx);
+
+        // A new occurrence, and even a deleted and recreated pod, stay quiet
+        assert!(alerts.check("prod/api-1", &crash_looping(5)).is_empty());
+        alerts.forget("prod/api-1");
+        assert!(alerts.check("prod/api-1", &crash_looping(6)).is_empty());
+        assert_quiet(&rx);
+
+        // Other pods have their own cooldown
+        let mut other = crash_looping(2);
+        other["metadata"]["name"] = json!("api-2");
+        assert_eq!(alerts.check("prod/api-2", &other).len(), 1);
+        assert_eq!(received(&rx)["pod"], "api-2");
+    }
+
+    #[test]
+    fn failed_webhook_is_reported() {
+        // Bind and drop to get a port nothing listens on
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut alerts = alerter(json!({
+            "when": "crash_loop",
+            "webhook": format!("http://127.0.0.1:{}/hook", port),
+        }));
+
+        alerts.check("prod/api-1", &crash_looping(1));
+        let result = alerts
+            .results_rx
+            .recv_timeout(Duration::from_secs(10))
+            .unwrap();
+        assert!(result.contains("failed"), "{}", result);
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        for rule in [
+            json!({ "when": "crash_loop" }),
+            json!({ "when": "restarts", "command": "true" }),
+            json!({ "when": "pending", "command": "true" }),
+            json!({ "when": "crash_loop", "cooldown": "soon", "command": "true" }),
+        ] {
+            let rule: AlertRule = serde_json::from_value(rule.clone()).unwrap();
+            assert!(Alerter::new(&[rule]).is_err());
+        }
+    }
+}
@@ -0,0 +1,589 @@
+use crate::kubectl::{find_container, find_pod};
+use anyhow::Result;
+use colored::*;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// One side of a copy: a local path or `<pod>:<path>`
+enum Location {
+    Local(PathBuf),
+    Remote { pod: String, path: String },
+}
+
+impl Location {
+    fn parse(spec: &str) -> Self {
+        // Anything that looks like a filesystem path stays local, so
+        // "./a:b" and "/tmp/x:y" are never treated as pods
+        if !spec.starts_with(['/', '.', '~']) {
+            if let Some((pod, path)) = spec.split_once(':') {
+                if !pod.is_empty() && !path.is_empty() {
+                    return Location::Remote {
+                        pod: pod.to_string(),
+                        path: path.to_string(),
+                    };
+                }
+            }
+        }
+        Location::Local(PathBuf::from(spec))
+    }
+}
+
+/// Where a copy ends up inside the pod
+struct Target {
+    pod: String,
+    ns: String,
+    container: Option<String>,
+}
+
+impl Target {
+    fn exec(&self, interactive: bool, argv: &[&str]) -> Command {
+        let mut cmd = Command::new("kubectl");
+        cmd.arg("exec");
+        if interactive {
+            cmd.arg("-i");
+        }
+        cmd.args([&self.pod, "-n", &self.ns]);
+        if let Some(container) = &self.container {
+            cmd.args(["-c", container]);
+        }
+        cmd.arg("--").args(argv);
+        cmd
+    }
+}
+
+/// Copy files to or from a pod (`kdbg cp <pod>:<path> <local>` or the reverse)
+pub fn copy_files(
+    src: &str,
+    dest: &str,
+    namespace: Option<String>,
+    container: Option<&str>,
+    use_cat: bool,
+) -> Result<()> {
+    let (pod_pattern, remote_path, local_path, download) =
+        match (Location::parse(src), Location::parse(dest)) {
+            (Location::Remote { pod, path }, Location::Local(local)) => (pod, path, local, true),
+            (Location::Local(local), Location::Remote { pod, path }) => (pod, path, local, false),
+            (Location::Remote { .. }, Location::Remote { .. }) => {
+                anyhow::bail!("Copying between two pods is not supported")
+            }
+            (Location::Local(_), Location::Local(_)) => {
+                anyhow::bail!("One side of the copy must be <pod>:<path>")
+            }
+        };
+
+    let (pod_name, ns) = find_pod(&pod_pattern, namespace)?;
+    let container = container
+        .map(|c| find_container(&pod_name, &ns, c))
+        .transpose()?;
+
+    let remote = format!("{}:{}", pod_name, remote_path);
+    let (from, to) = if download {
+        (remote.clone(), local_path.display().to_string())
+    } else {
+        (local_path.display().to_string(), remote.clone())
+    };
+    println!(
+        "{} Copying {} -> {} (namespace: {})",
+        "[INFO]".cyan(),
+        from.bold(),
+        to.bold(),
+        ns.bright_black()
+    );
+    println!("{}", "-".repeat(100));
+
+    let target = Target {
+        pod: pod_name,
+        ns,
+        container,
+    };
+
+    let bytes = match (download, use_cat) {
+        (true, false) => download_tar(&target, &remote_path, &local_path)?,
+        (true, true) => download_cat(&target, &remote_path, &local_path)?,
+        (false, false) => upload_tar(&target, &local_path, &remote_path)?,
+        (false, true) => upload_cat(&target, &local_path, &remote_path)?,
+    };
+
+    println!(
+        "{} Copied {} ({})",
+        "[SUCCESS]".green(),
+        to.bold(),
+        format_bytes(bytes)
+    );
+
+    Ok(())
+}
+
+fn download_tar(target: &Target, remote: &str, local: &Path) -> Result<u64> {
+    let (remote_dir, remote_base) = split_remote(remote);
+
+    // Extract into the destination directory if it exists, otherwise into a
+    // scratch directory next to it and rename, so `cp pod:/a/b ./c` gives ./c.
+    // Whatever was written is removed again if the copy fails.
+    let (extract_dir, rename_to, _scratch, mut partial) = if local.is_dir() {
+        let dest = local.join(remote_base);
+        let partial = RemoveOnDrop((!dest.exists()).then_some(dest));
+        (local.to_path_buf(), None, RemoveOnDrop(None), partial)
+    } else {
+        let parent = match local.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let scratch = parent.join(format!(".kdbg-cp-{}", std::process::id()));
+        fs::create_dir_all(&scratch)?;
+        (
+            scratch.clone(),
+            Some(local.to_path_buf()),
+            RemoveOnDrop(Some(scratch)),
+            RemoveOnDrop(None),
+        )
+    };
+
+    let mut remote_tar = target
+        .exec(false, &["tar", "cf", "-", "-C", remote_dir, remote_base])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut local_tar = Command::new("tar")
+        .arg("xf")
+        .arg("-")
+        .arg("-C")
+        .arg(&extract_dir)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stderr = collect_stderr(&mut remote_tar);
+    let local_stderr = collect_stderr(&mut local_tar);
+    let bytes = pump(
+        remote_tar.stdout.take().unwrap(),
+        local_tar.stdin.take().unwrap(),
+        None,
+    )?;
+
+    let remote_status = remote_tar.wait()?;
+    let local_status = local_tar.wait()?;
+    let stderr = stderr.join().unwrap_or_default();
+    let local_stderr = local_stderr.join().unwrap_or_default();
+
+    if !remote_status.success() {
+        return Err(exec_error(&stderr, "tar"));
+    }
+    if !local_status.success() {
+        anyhow::bail!("Failed to extract archive locally: {}", local_stderr.trim());
+    }
+
+    if let Some(dest) = rename_to {
+        fs::rename(extract_dir.join(remote_base), &dest)?;
+    }
+    partial.disarm();
+
+    Ok(bytes)
+}
+
+fn download_cat(target: &Target, remote: &str, local: &Path) -> Result<u64> {
+    let dest = if local.is_dir() {
+        local.join(split_remote(remote).1)
+    } else {
+        local.to_path_buf()
+    };
+
+    // Stream into a file next to the destination and only replace it once
+    // cat succeeded, so a failed copy leaves an existing file as it was
+    let name = dest
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp = dest.with_file_name(format!(".kdbg-cp-{}-{}", std::process::id(), name));
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&temp)?;
+    let mut partial = RemoveOnDrop(Some(temp.clone()));
+
+    let mut child = target
+        .exec(false, &["cat", remote])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stderr = collect_stderr(&mut child);
+    let bytes = pump(child.stdout.take().unwrap(), file, None)?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(exec_error(&stderr.join().unwrap_or_default(), "cat"));
+    }
+    fs::rename(&temp, &dest)?;
+    partial.disarm();
+
+    Ok(bytes)
+}
+
+/// Removes a file or directory when dropped, unless disarmed first, so
+/// every early return of a failed copy cleans up after itself
+struct RemoveOnDrop(Option<PathBuf>);
+
+impl RemoveOnDrop {
+    fn disarm(&mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        if let Some(path) = self.0.take() {
+            let _ = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+        }
+    }
+}
+
+fn upload_tar(target: &Target, local: &Path, remote: &str) -> Result<u64> {
+    let local = local.canonicalize()?;
+    let local_dir = local.parent().unwrap_or(Path::new("/"));
+    let local_base = local
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Invalid local path"))?;
+
+    // "pod:/dir/" copies into the directory; otherwise the last component
+    // is the new name
+    let (remote_dir, remote_name) = if remote.ends_with('/') {
+        (remote.trim_end_matches('/'), local_base)
+    } else {
+        split_remote(remote)
+    };
+    let remote_dir = if remote_dir.is_empty() {
+        "/"
+    } else {
+        remote_dir
+    };
+
+    let mut local_tar = Command::new("tar")
+        .arg("cf")
+        .arg("-")
+        .arg("-C")
+        .arg(local_dir)
+        .arg(local_base)
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let extract = extract_command(remote_dir, local_base, remote_name);
+    let extract: Vec<&str> = extract.iter().map(String::as_str).collect();
+    let mut remote_tar = target
+        .exec(true, &extract)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stderr = collect_stderr(&mut remote_tar);
+    let bytes = pump(
+        local_tar.stdout.take().unwrap(),
+        remote_tar.stdin.take().unwrap(),
+        Some(disk_usage(&local)),
+    )?;
+
+    let local_status = local_tar.wait()?;
+    let remote_status = remote_tar.wait()?;
+    if !local_status.success() {
+        anyhow::bail!("Failed to archive {}", local.display());
+    }
+    if !remote_status.success() {
+        return Err(exec_error(&stderr.join().unwrap_or_default(), "tar"));
+    }
+
+    Ok(bytes)
+}
+
+/// Command unpacking an upload in the pod. A renamed upload is unpacked in a
+/// scratch directory and moved into place from there, so an existing file
+/// with the local name is never overwritten.
+fn extract_command(remote_dir: &str, local_base: &str, remote_name: &str) -> Vec<String> {
+    if remote_name == local_base {
+        return ["tar", "xf", "-", "-C", remote_dir]
+            .map(String::from)
+            .to_vec();
+    }
+
+    let scratch = format!(
+        "{}/.kdbg-cp-{}",
+        remote_dir.trim_end_matches('/'),
+        std::process::id()
+    );
+    let dest = format!("{}/{}", remote_dir.trim_end_matches('/'), remote_name);
+    vec![
+        "sh".to_string(),
+        "-c".to_string(),
+        r#"mkdir -- "$1" && tar xf - -C "$1" && mv -- "$1/$2" "$3"; status=$?; rm -rf -- "$1"; exit $status"#
+            .to_string(),
+        "sh".to_string(),
+        scratch,
+        local_base.to_string(),
+        dest,
+    ]
+}
+
+fn upload_cat(target: &Target, local: &Path, remote: &str) -> Result<u64> {
+    if local.is_dir() {
+        anyhow::bail!("--cat can only copy single files, not directories");
+    }
+
+    let remote = if remote.ends_with('/') {
+        let name = local
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid local path"))?;
+        format!("{}{}", remote, name)
+    } else {
+        remote.to_string()
+    };
+
+    let mut child = target
+        .exec(true, &["sh", "-c", "cat > \"$1\"", "sh", &remote])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stderr = collect_stderr(&mut child);
+    let file = fs::File::open(local)?;
+    let size = file.metadata()?.len();
+    let bytes = pump(file, child.stdin.take().unwrap(), Some(size))?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(exec_error(&stderr.join().unwrap_or_default(), "sh"));
+    }
+
+    Ok(bytes)
+}
+
+/// Turn a failed exec into a clear error, calling out missing binaries
+fn exec_error(stderr: &str, binary: &str) -> anyhow::Error {
+    let missing = stderr.contains("executable file not found")
+        || stderr.contains(&format!("{}: not found", binary))
+        || stderr.contains("exit code 127");
+
+    if missing && binary == "tar" {
+        anyhow::anyhow!(
+            "The container has no `tar` binary. Retry with --cat to stream a single file through cat"
+        )
+    } else if missing {
+        anyhow::anyhow!("The container has no `{}` binary", binary)
+    } else {
+        anyhow::anyhow!("Copy failed: {}", stderr.trim())
+    }
+}
+
+fn collect_stderr(child: &mut Child) -> thread::JoinHandle<String> {
+    let stderr = child.stderr.take();
+    thread::spawn(move || {
+        let mut buf = String::new();
+        if let Some(mut stderr) = stderr {
+            let _ = stderr.read_to_string(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Copy between streams, showing progress on a terminal
+fn pump(mut reader: impl Read, mut writer: impl Write, total: Option<u64>) -> Result<u64> {
+    let show_progress = io::stderr().is_terminal();
+    let started = Instant::now();
+    let mut last_draw = Instant::now();
+    let mut buf = [0u8; 64 * 1024];
+    let mut copied = 0u64;
+
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        writer.write_all(&buf[..n])?;
+        copied += n as u64;
+
+        if show_progress && last_draw.elapsed() >= Duration::from_millis(200) {
+            draw_progress(copied, total, started.elapsed());
+            last_draw = Instant::now();
+        }
+    }
+    writer.flush()?;
+
+    if show_progress && started.elapsed() >= Duration::from_millis(200) {
+        draw_progress(copied, total, started.elapsed());
+        eprintln!();
+    }
+
+    Ok(copied)
+}
+
+fn draw_progress(copied: u64, total: Option<u64>, elapsed: Duration) {
+    let rate = copied as f64 / elapsed.as_secs_f64().max(0.001);
+    let percent = total
+        .filter(|t| *t > 0)
+        .map(|t| format!(" {:>3}%", (copied * 100 / t).min(100)))
+        .unwrap_or_default();
+    eprint!(
+        "\r  {} transferred{} ({}/s)   ",
+        format_bytes(copied).cyan(),
+        percent,
+        format_bytes(rate as u64)
+    );
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Total size of a file or directory tree (approximate progress target)
+fn disk_usage(path: &Path) -> u64 {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::read_dir(path)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|entry| disk_usage(&entry.path()))
+                    .sum()
+            })
+            .unwrap_or(0),
+        Ok(meta) => meta.len(),
+        Err(_) => 0,
+    }
+}
+
+/// Split a remote path into (directory, last component)
+fn split_remote(path: &str) -> (&str, &str) {
+    let trimmed = path.trim_end_matches('/');
+    match trimmed.rsplit_once('/') {
+        Some(("", base)) => ("/", base),
+        Some((dir, base)) => (dir, base),
+        None => (".", trimmed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `extract_command` locally, as the pod would, on an archive of
+    /// `src/<name>`
+    fn extract(src: &Path, name: &str, remote_dir: &Path, remote_name: &str) -> bool {
+        let argv = extract_command(remote_dir.to_str().unwrap(), name, remote_name);
+        let archive = Command::new("tar")
+            .arg("cf")
+            .arg("-")
+            .arg("-C")
+            .arg(src)
+            .arg(name)
+            .output()
+            .unwrap();
+        let mut child = Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(&archive.stdout)
+            .unwrap();
+        child.wait().unwrap().success()
+    }
+
+    fn scratch_dir(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("kdbg-cp-test-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("local")).unwrap();
+        fs::create_dir_all(dir.join("remote")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn renamed_upload_leaves_the_local_name_alone() {
+        let dir = scratch_dir("rename");
+        fs::write(dir.join("local/config"), "new").unwrap();
+        fs::write(dir.join("remote/config"), "live").unwrap();
+
+        assert!(extract(
+            &dir.join("local"),
+            "config",
+            &dir.join("remote"),
+            "config.bak"
+        ));
+
+        assert_eq!(
+            fs::read_to_string(dir.join("remote/config")).unwrap(),
+            "live"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("remote/config.bak")).unwrap(),
+            "new"
+        );
+        let mut left: Vec<_> = fs::read_dir(dir.join("remote"))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["config", "config.bak"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn upload_under_the_same_name_extracts_in_place() {
+        let dir = scratch_dir("same");
+        fs::write(dir.join("local/config"), "new").unwrap();
+
+        assert_eq!(
+            extract_command("/etc/app", "config", "config"),
+            ["tar", "xf", "-", "-C", "/etc/app"]
+        );
+        assert!(extract(
+            &dir.join("local"),
+            "config",
+            &dir.join("remote"),
+            "config"
+        ));
+        assert_eq!(
+            fs::read_to_string(dir.join("remote/config")).unwrap(),
+            "new"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_renamed_upload_removes_its_scratch_dir() {
+        let dir = scratch_dir("fail");
+        fs::write(dir.join("local/config"), "new").unwrap();
+
+        // The destination's parent is missing, so the move fails
+        assert!(!extract(
+            &dir.join("local"),
+            "config",
+            &dir.join("remote"),
+            "missing/config"
+        ));
+        assert_eq!(fs::read_dir(dir.join("remote")).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
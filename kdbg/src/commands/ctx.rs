@@ -1,53 +1,443 @@
-use anyhow::Result;
+use crate::utils::{get_kdbg_dir, pick};
+use anyhow::{Context, Result};
 use colored::*;
+use serde_json::Value;
+use std::io::{IsTerminal, Write};
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
 use std::process::Command;
 
+/// Switch kubectl context. The name is fuzzy matched; "-" goes back to the
+/// previous context; no name lists contexts, or offers a picker on a TTY.
 pub fn switch_context(context: Option<String>) -> Result<()> {
-    if let Some(ctx) = context {
-        println!("{} Switching to context: {}", "[INFO]".cyan(), ctx.bold());
+    let contexts = list_contexts()?;
+    let current = current_context().unwrap_or_default();
 
-        let status = Command::new("kubectl")
-            .args(["config", "use-context", &ctx])
-            .status()?;
+    let target = match context.as_deref() {
+        Some(pattern) => resolve_context(pattern, &contexts, &current)?,
+        None => {
+            println!("{} Available contexts:", "[INFO]".cyan());
+            println!("{}", "-".repeat(100));
 
-        if !status.success() {
-            anyhow::bail!("Failed to switch context");
+            if interactive() {
+                match pick("Switch to context", &contexts, Some(&current)) {
+                    Some(index) => contexts[index].clone(),
+                    None => return Ok(()),
+                }
+            } else {
+                for ctx in &contexts {
+                    if *ctx == current {
+                        println!("  {} {}", "●".green(), ctx.green().bold());
+                    } else {
+                        println!("  ○ {}", ctx);
+                    }
+                }
+
+                println!();
+                println!("{} Use 'kdbg ctx <name>' to switch", "[TIP]".yellow());
+                return Ok(());
+            }
         }
+    };
+
+    if target == current {
+        println!("{} Already on context: {}", "[INFO]".cyan(), target.bold());
+        return Ok(());
+    }
+
+    println!(
+        "{} Switching to context: {}",
+        "[INFO]".cyan(),
+        target.bold()
+    );
+
+    let status = Command::new("kubectl")
+        .args(["config", "use-context", &target])
+        .status()?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to switch context");
+    }
+
+    // Remember where we came from for 'kdbg ctx -'
+    if !current.is_empty() {
+        std::fs::create_dir_all(get_kdbg_dir())?;
+        std::fs::write(previous_context_file(), format!("{}\n", current))?;
+    }
 
-        println!("{} Context switched successfully", "[SUCCESS]".green());
+    println!("{} Context switched successfully", "[SUCCESS]".green());
+
+    Ok(())
+}
+
+/// Set the namespace of the current context, fuzzy matched against the
+/// cluster's namespaces. No name shows them, or offers a picker on a TTY.
+pub fn switch_namespace(namespace: Option<String>) -> Result<()> {
+    let context = current_context()?;
+    let current = current_namespace();
+
+    let output = Command::new("kubectl")
+        .args(["get", "namespaces", "-o", "json"])
+        .output()?;
+    let namespaces: Vec<String> = if output.status.success() {
+        let json: Value = serde_json::from_slice(&output.stdout)?;
+        json["items"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|ns| ns["metadata"]["name"].as_str())
+            .map(String::from)
+            .collect()
     } else {
-        println!("{} Available contexts:", "[INFO]".cyan());
-        println!("{}", "-".repeat(100));
+        let error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        // Without cluster-wide list rights, an exact name can still be set
+        let Some(name) = &namespace else {
+            anyhow::bail!("Failed to list namespaces: {}", error);
+        };
+        println!(
+            "{} Can't list namespaces ({}); using '{}' as given",
+            "[WARN]".yellow(),
+            error,
+            name
+        );
+        vec![name.clone()]
+    };
 
-        let output = Command::new("kubectl")
-            .args(["config", "get-contexts", "-o", "name"])
-            .output()?;
+    let target = match namespace.as_deref() {
+        Some(pattern) => fuzzy_match("namespace", &namespaces, pattern, Some(&current))?,
+        None => {
+            println!(
+                "{} Namespaces in context {}:",
+                "[INFO]".cyan(),
+                context.bold()
+            );
+            println!("{}", "-".repeat(100));
 
-        if !output.status.success() {
-            anyhow::bail!("Failed to get contexts");
+            if interactive() {
+                match pick("Switch to namespace", &namespaces, Some(&current)) {
+                    Some(index) => namespaces[index].clone(),
+                    None => return Ok(()),
+                }
+            } else {
+                for ns in &namespaces {
+                    if *ns == current {
+                        println!("  {} {}", "●".green(), ns.green().bold());
+                    } else {
+                        println!("  ○ {}", ns);
+                    }
+                }
+
+                println!();
+                println!("{} Use 'kdbg ns <name>' to switch", "[TIP]".yellow());
+                return Ok(());
+            }
         }
+    };
 
-        let contexts = String::from_utf8_lossy(&output.stdout);
+    println!(
+        "{} Setting namespace {} on context {}",
+        "[INFO]".cyan(),
+        target.bold(),
+        context.bright_black()
+    );
 
-        let current_output = Command::new("kubectl")
-            .args(["config", "current-context"])
-            .output()?;
+    let status = Command::new("kubectl")
+        .args([
+            "config",
+            "set-context",
+            "--current",
+            &format!("--namespace={}", target),
+        ])
+        .stdout(std::process::Stdio::null())
+        .status()?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to set namespace");
+    }
+
+    println!("{} Namespace switched successfully", "[SUCCESS]".green());
+
+    Ok(())
+}
+
+/// Run a subshell whose KUBECONFIG is a temporary file holding only the
+/// given context, so switching there can't affect other terminals. With
+/// `export`, print shell code to do the same in the current shell instead.
+pub fn context_shell(pattern: &str, export: bool) -> Result<()> {
+    let contexts = list_contexts()?;
+    let current = current_context().unwrap_or_default();
+    let context = resolve_context(pattern, &contexts, &current)?;
+
+    // --flatten inlines certificates, so the file stands on its own
+    let output = Command::new("kubectl")
+        .args([
+            "config",
+            "view",
+            "--minify",
+            "--flatten",
+            "--raw",
+            "--context",
+            &context,
+        ])
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to extract context '{}': {}",
+            context,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    remove_stale_kubeconfigs();
+    let path = write_kubeconfig(&context, &output.stdout)?;
+
+    if export {
+        // Rename the file after the shell that evals this, rather than
+        // setting an EXIT trap that would replace the user's own; it's
+        // cleaned up by a later run once that shell is gone
+        let owned = path.with_file_name(kubeconfig_name(&context));
+        println!(
+            "mv -f -- {0} {1}-$$ && export KUBECONFIG={1}-$$ KDBG_CONTEXT={2}",
+            shell_quote(&path.display().to_string()),
+            shell_quote(&owned.display().to_string()),
+            shell_quote(&context)
+        );
+        return Ok(());
+    }
+
+    let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string());
+    println!(
+        "{} Starting {} on context {} (other terminals are unaffected)",
+        "[INFO]".cyan(),
+        shell,
+        context.bold()
+    );
+    println!(
+        "{} Exit the shell to leave; $KDBG_CONTEXT holds the context name",
+        "[TIP]".yellow()
+    );
+
+    let child = Command::new(&shell)
+        .env("KUBECONFIG", &path)
+        .env("KDBG_CONTEXT", &context)
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => {
+            let _ = std::fs::remove_file(&path);
+            return Err(err).with_context(|| format!("Failed to start {}", shell));
+        }
+    };
+
+    // Ctrl+C is meant for the subshell, and a closed terminal or kill hits
+    // it too; kdbg has to outlive it to clean up. Only SIGKILL gets past
+    // this, which the next run's sweep covers.
+    const SIGNALS: [libc::c_int; 4] = [libc::SIGINT, libc::SIGQUIT, libc::SIGHUP, libc::SIGTERM];
+    let previous = SIGNALS.map(|sig| unsafe { libc::signal(sig, libc::SIG_IGN) });
+    let status = child.wait();
+    for (sig, handler) in SIGNALS.iter().zip(previous) {
+        unsafe {
+            libc::signal(*sig, handler);
+        }
+    }
+    let _ = std::fs::remove_file(&path);
+    status?;
+
+    println!(
+        "{} Left the {} shell, temporary kubeconfig removed",
+        "[INFO]".cyan(),
+        context.bold()
+    );
+
+    Ok(())
+}
 
-        let current = String::from_utf8_lossy(&current_output.stdout)
-            .trim()
-            .to_string();
+/// Private directory for per-shell kubeconfigs (they hold credentials)
+fn kubeconfig_dir() -> Result<PathBuf> {
+    let dir = get_kdbg_dir().join("kubeconfigs");
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    // The mode only applies on creation; tighten one made some other way
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+    Ok(dir)
+}
 
-        for ctx in contexts.lines() {
-            if ctx == current {
-                println!("  {} {}", "●".green(), ctx.green().bold());
+/// File name for a context's kubeconfig, before the owning pid is added
+fn kubeconfig_name(context: &str) -> String {
+    context
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
             } else {
-                println!("  ○ {}", ctx);
+                '_'
             }
+        })
+        .collect()
+}
+
+/// Write a kubeconfig readable only by the user, named after this process
+fn write_kubeconfig(context: &str, contents: &[u8]) -> Result<PathBuf> {
+    let path = kubeconfig_dir()?.join(format!(
+        "{}-{}",
+        kubeconfig_name(context),
+        std::process::id()
+    ));
+
+    // A leftover from an earlier process with the same pid is never reused
+    let _ = std::fs::remove_file(&path);
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(contents)?;
+
+    Ok(path)
+}
+
+/// Remove kubeconfigs whose owning process (the pid suffix) has exited. Very
+/// recent files are kept: an exported one is briefly owned by a finished
+/// kdbg until the shell renames it.
+fn remove_stale_kubeconfigs() {
+    let Ok(entries) = std::fs::read_dir(get_kdbg_dir().join("kubeconfigs")) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(pid) = name
+            .rsplit_once('-')
+            .and_then(|(_, pid)| pid.parse::<libc::pid_t>().ok())
+        else {
+            continue;
+        };
+        let settled = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.elapsed().ok())
+            .is_some_and(|age| age.as_secs() >= 60);
+        if !settled || pid <= 0 || process_alive(pid) {
+            continue;
         }
+        let _ = std::fs::remove_file(entry.path());
+    }
+}
 
-        println!();
-        println!("{} Use 'kdbg ctx <name>' to switch", "[TIP]".yellow());
+fn process_alive(pid: libc::pid_t) -> bool {
+    let alive = unsafe { libc::kill(pid, 0) } == 0;
+    alive || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Quote a string for POSIX shells
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+pub(crate) fn list_contexts() -> Result<Vec<String>> {
+    let output = Command::new("kubectl")
+        .args(["config", "get-contexts", "-o", "name"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("Failed to get contexts");
     }
 
-    Ok(())
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(String::from)
+        .collect())
+}
+
+pub(crate) fn current_context() -> Result<String> {
+    let output = Command::new("kubectl")
+        .args(["config", "current-context"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("No current context set");
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Namespace set on the current context ("default" when none is)
+fn current_namespace() -> String {
+    Command::new("kubectl")
+        .args(["config", "view", "--minify", "-o", "jsonpath={..namespace}"])
+        .output()
+        .ok()
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|ns| !ns.is_empty())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+/// Context named by `pattern`: "-" for the previous one, otherwise fuzzy
+/// matched
+fn resolve_context(pattern: &str, contexts: &[String], current: &str) -> Result<String> {
+    if pattern != "-" {
+        return fuzzy_match("context", contexts, pattern, Some(current));
+    }
+
+    let previous = std::fs::read_to_string(previous_context_file())
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .context("No previous context to go back to")?;
+    if !contexts.contains(&previous) {
+        anyhow::bail!("Previous context '{}' no longer exists", previous);
+    }
+    Ok(previous)
+}
+
+fn previous_context_file() -> PathBuf {
+    get_kdbg_dir().join("previous-context")
+}
+
+/// Exact name first, then case-insensitive substring. Several matches are
+/// offered in a picker on a TTY, otherwise listed.
+fn fuzzy_match(
+    kind: &str,
+    candidates: &[String],
+    pattern: &str,
+    current: Option<&str>,
+) -> Result<String> {
+    if candidates.iter().any(|c| c == pattern) {
+        return Ok(pattern.to_string());
+    }
+
+    let needle = pattern.to_lowercase();
+    let matches: Vec<String> = candidates
+        .iter()
+        .filter(|c| c.to_lowercase().contains(&needle))
+        .cloned()
+        .collect();
+
+    match matches.len() {
+        0 => anyhow::bail!("No {}s found matching '{}'", kind, pattern),
+        1 => Ok(matches[0].clone()),
+        _ => {
+            // stderr, so `eval "$(kdbg ctx --shell x --export)"` never runs it
+            eprintln!("{} Multiple {}s found:", "[INFO]".yellow(), kind);
+            if interactive() {
+                if let Some(index) = pick(&format!("Switch to {}", kind), &matches, current) {
+                    return Ok(matches[index].clone());
+                }
+            } else {
+                for name in &matches {
+                    eprintln!("  - {}", name.cyan());
+                }
+            }
+            anyhow::bail!("Please be more specific")
+        }
+    }
+}
+
+fn interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
 }
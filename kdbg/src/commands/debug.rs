@@ -1,15 +1,113 @@
-use anyhow::Result;
+use crate::commands::nodes::find_node;
+use crate::config::{Config, DebugProfile};
+use crate::kubectl::{find_container, find_pod, get_nodes_json, get_pod_json};
+use crate::recorder::{run_recorded, SessionMeta};
+use crate::utils::{format_duration, get_kdbg_dir, parse_duration};
+use anyhow::{Context, Result};
 use colored::*;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::os::unix::process::CommandExt;
 use std::process::{Command, Stdio};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-pub fn debug_pod(image: &str, namespace: &str) -> Result<()> {
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+const DEFAULT_IMAGE: &str = "busybox";
+
+/// Labels put on every pod kdbg creates, so `kdbg debug gc` can find them
+const MANAGED_BY_LABEL: &str = "app.kubernetes.io/managed-by";
+const CREATED_BY_LABEL: &str = "kdbg/created-by";
+const CREATED_AT_LABEL: &str = "kdbg/created-at";
+const TTL_LABEL: &str = "kdbg/ttl";
+
+/// Minimum time between automatic background sweeps
+const AUTO_GC_INTERVAL: u64 = 15 * 60;
+
+/// How `kdbg debug` was asked to debug
+pub struct DebugOptions {
+    pub image: Option<String>,
+    pub namespace: Option<String>,
+    /// Ephemeral mode: container whose process namespace to join.
+    /// Copy mode: container to change in the copy.
+    pub target: Option<String>,
+    pub copy: bool,
+    pub node: Option<String>,
+    /// Debug pod profile from the config (or a built-in one)
+    pub profile: Option<String>,
+    /// Print the generated pod manifest instead of creating it
+    pub dry_run: bool,
+    /// Replacement command for the copied container
+    pub command: Vec<String>,
+    pub record: bool,
+}
+
+/// Debug a pod with an ephemeral container (or a copy of it with `--copy`),
+/// or start a debug pod built from a profile, optionally on a given node
+pub fn debug_pod(pod_pattern: Option<&str>, opts: &DebugOptions) -> Result<()> {
+    let config = Config::load()?;
+    let profile = match &opts.profile {
+        Some(name) => Some(config.debug.profile(name).with_context(|| {
+            format!(
+                "No debug profile '{}' (available: {})",
+                name,
+                config.debug.profile_names().join(", ")
+            )
+        })?),
+        None => None,
+    };
+    let ttl = parse_duration(&config.debug.ttl)
+        .with_context(|| format!("Invalid debug ttl '{}'", config.debug.ttl))?;
+
+    match pod_pattern {
+        Some(pattern) if opts.copy => debug_copy(pattern, profile.as_ref(), ttl, opts),
+        Some(pattern) => debug_ephemeral(pattern, profile.as_ref(), opts),
+        None => debug_new_pod(profile, ttl, opts),
+    }
+}
 
-    let pod_name = format!("debug-{}", timestamp);
+/// Create a debug pod from a profile, open a shell in it and delete it on
+/// exit. `--node` without a profile gives a privileged pod with the host's
+/// namespaces and root filesystem mounted at /host.
+fn debug_new_pod(profile: Option<DebugProfile>, ttl: u64, opts: &DebugOptions) -> Result<()> {
+    let node = match &opts.node {
+        Some(pattern) => {
+            let nodes = get_nodes_json()?;
+            let empty_vec = vec![];
+            let node = find_node(nodes["items"].as_array().unwrap_or(&empty_vec), pattern)?;
+            Some(
+                node["metadata"]["name"]
+                    .as_str()
+                    .unwrap_or("unknown")
+                    .to_string(),
+            )
+        }
+        None => None,
+    };
+    let host = node.is_some() && profile.is_none();
+
+    let mut profile = profile.unwrap_or_default();
+    if let Some(image) = &opts.image {
+        profile.image = Some(image.clone());
+    }
+    let image = profile
+        .image
+        .clone()
+        .unwrap_or_else(|| DEFAULT_IMAGE.to_string());
+
+    let namespace = opts.namespace.as_deref().unwrap_or("default");
+    let pod_name = match (&node, &opts.profile) {
+        (_, Some(name)) => debug_name("debug", name),
+        (Some(node), None) => debug_name(&format!("node-debugger-{}", node), ""),
+        (None, None) => format!("debug-{}", timestamp()),
+    };
+
+    let mut manifest = build_manifest(&pod_name, namespace, &profile, node.as_deref(), host);
+    manifest["metadata"]["labels"] = json!(kdbg_labels(ttl));
+    if opts.dry_run {
+        println!("{}", serde_json::to_string_pretty(&manifest)?);
+        return Ok(());
+    }
 
     println!(
         "{} Creating debug pod: {} (image: {}, namespace: {})",
@@ -18,34 +116,594 @@ pub fn debug_pod(image: &str, namespace: &str) -> Result<()> {
         image.yellow(),
         namespace.bright_black()
     );
+    if let Some(node) = &node {
+        println!("{} Scheduling on node {}", "[INFO]".cyan(), node.cyan());
+    }
+    if host {
+        println!(
+            "{} Host filesystem is at /host (try: chroot /host)",
+            "[INFO]".cyan()
+        );
+    }
+
+    create_pod(&manifest)?;
+    let result = wait_and_shell(&pod_name, namespace, Some("debugger"), opts.record);
+    delete_pod(&pod_name, namespace);
+
+    result
+}
+
+/// Generate the debug pod manifest
+fn build_manifest(
+    name: &str,
+    namespace: &str,
+    profile: &DebugProfile,
+    node: Option<&str>,
+    host: bool,
+) -> Value {
+    let command = if profile.command.is_empty() {
+        vec!["sleep".to_string(), "infinity".to_string()]
+    } else {
+        profile.command.clone()
+    };
+
+    let mut container = json!({
+        "name": "debugger",
+        "image": profile.image.as_deref().unwrap_or(DEFAULT_IMAGE),
+        "command": command,
+    });
+    if !profile.env.is_empty() {
+        container["env"] = profile
+            .env
+            .iter()
+            .map(|(name, value)| json!({ "name": name, "value": value }))
+            .collect();
+    }
+    if let Some(security_context) = &profile.security_context {
+        container["securityContext"] = security_context.clone();
+    }
+    if let Some(resources) = &profile.resources {
+        container["resources"] = resources.clone();
+    }
+
+    let mut spec = json!({
+        "restartPolicy": "Never",
+        "terminationGracePeriodSeconds": 0,
+    });
+    if !profile.node_selector.is_empty() {
+        spec["nodeSelector"] = json!(profile.node_selector);
+    }
+    if !profile.tolerations.is_empty() {
+        spec["tolerations"] = json!(profile.tolerations);
+    }
+    if let Some(service_account) = &profile.service_account {
+        spec["serviceAccountName"] = json!(service_account);
+    }
+    if let Some(node) = node {
+        spec["nodeName"] = json!(node);
+    }
+
+    if host {
+        spec["hostPID"] = json!(true);
+        spec["hostIPC"] = json!(true);
+        spec["hostNetwork"] = json!(true);
+        // Node debugging has to get onto tainted nodes too
+        spec["tolerations"] = json!([{ "operator": "Exists" }]);
+        spec["volumes"] = json!([{ "name": "host-root", "hostPath": { "path": "/" } }]);
+        container["securityContext"] = json!({ "privileged": true });
+        container["volumeMounts"] = json!([{ "name": "host-root", "mountPath": "/host" }]);
+    }
+
+    spec["containers"] = json!([container]);
+
+    json!({
+        "apiVersion": "v1",
+        "kind": "Pod",
+        "metadata": { "name": name, "namespace": namespace },
+        "spec": spec,
+    })
+}
+
+/// Attach an ephemeral container that shares the target's process namespace,
+/// so distroless containers can be inspected via /proc/1/root
+fn debug_ephemeral(
+    pod_pattern: &str,
+    profile: Option<&DebugProfile>,
+    opts: &DebugOptions,
+) -> Result<()> {
+    let (pod_name, ns) = find_pod(pod_pattern, opts.namespace.clone())?;
+    let target = opts
+        .target
+        .as_deref()
+        .map(|t| find_container(&pod_name, &ns, t))
+        .transpose()?;
+    let image = opts
+        .image
+        .as_deref()
+        .or(profile.and_then(|p| p.image.as_deref()))
+        .unwrap_or(DEFAULT_IMAGE);
+    let container = format!("kdbg-debug-{}", timestamp());
+
     println!(
-        "{} Pod will be deleted when you exit the shell",
+        "{} Attaching ephemeral container {} to {} (image: {}, namespace: {})",
+        "[INFO]".cyan(),
+        container.bold(),
+        pod_name.bold(),
+        image.yellow(),
+        ns.bright_black()
+    );
+    if let Some(target) = &target {
+        println!(
+            "{} Sharing the process namespace of {}; its filesystem is at /proc/1/root",
+            "[INFO]".cyan(),
+            target.cyan()
+        );
+    }
+    println!(
+        "{} Ephemeral containers stay in the pod spec until the pod is replaced",
         "[INFO]".yellow()
     );
     println!("{}", "-".repeat(100));
 
+    let mut cmd = Command::new("kubectl");
+    cmd.args([
+        "debug", &pod_name, "-n", &ns, "-it", "--image", image, "-c", &container,
+    ]);
+    if let Some(target) = &target {
+        cmd.arg(format!("--target={}", target));
+    }
+    // Ephemeral containers can't have resources, and the pod is already
+    // scheduled, so only container-level settings apply
+    let custom = match profile {
+        Some(profile) => apply_profile(&mut cmd, profile, opts, "ephemeral", false)?,
+        None => None,
+    };
+    cmd.args(["--", "/bin/sh"]);
+
+    let status = run_session(cmd, &pod_name, &ns, Some(&container), opts.record);
+    if let Some(custom) = custom {
+        let _ = fs::remove_file(custom);
+    }
+    if !status?.success() {
+        anyhow::bail!("Failed to attach debug container");
+    }
+
+    Ok(())
+}
+
+/// Clone the pod with a changed image and/or command, then open a shell in
+/// the copy. The copy is deleted when the shell exits.
+fn debug_copy(
+    pod_pattern: &str,
+    profile: Option<&DebugProfile>,
+    ttl: u64,
+    opts: &DebugOptions,
+) -> Result<()> {
+    let (pod_name, ns) = find_pod(pod_pattern, opts.namespace.clone())?;
+    let container = match &opts.target {
+        Some(target) => find_container(&pod_name, &ns, target)?,
+        None => get_pod_json(&pod_name, &ns)?["spec"]["containers"][0]["name"]
+            .as_str()
+            .context("Pod has no containers")?
+            .to_string(),
+    };
+    let copy_name = debug_name(&pod_name, "debug");
+    let image = opts
+        .image
+        .as_deref()
+        .or(profile.and_then(|p| p.image.as_deref()));
+
+    println!(
+        "{} Copying {} to {} (container: {}, namespace: {})",
+        "[INFO]".cyan(),
+        pod_name.bold(),
+        copy_name.bold(),
+        container.cyan(),
+        ns.bright_black()
+    );
+    if let Some(image) = image {
+        println!("  image   -> {}", image.yellow());
+    }
+    if !opts.command.is_empty() {
+        println!("  command -> {}", opts.command.join(" ").yellow());
+    }
+
+    let mut cmd = Command::new("kubectl");
+    cmd.args([
+        "debug",
+        &pod_name,
+        "-n",
+        &ns,
+        &format!("--copy-to={}", copy_name),
+        &format!("--container={}", container),
+        "--share-processes",
+        "--attach=false",
+    ]);
+    if let Some(image) = image {
+        cmd.arg(format!("--image={}", image));
+    }
+    let custom = match profile {
+        Some(profile) => apply_profile(&mut cmd, profile, opts, "--copy", true)?,
+        None => None,
+    };
+    if !opts.command.is_empty() {
+        cmd.arg("--").args(&opts.command);
+    }
+
+    let status = cmd.status();
+    if let Some(custom) = custom {
+        let _ = fs::remove_file(custom);
+    }
+    if !status?.success() {
+        anyhow::bail!("Failed to copy pod");
+    }
+
+    // kubectl debug has no way to set labels on the copy, so add them after
+    let labels: Vec<String> = kdbg_labels(ttl)
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect();
+    let _ = Command::new("kubectl")
+        .args(["label", "pod", &copy_name, "-n", &ns])
+        .args(&labels)
+        .stdout(Stdio::null())
+        .status();
+
+    let result = wait_and_shell(&copy_name, &ns, Some(&container), opts.record);
+    if result.is_err() && opts.command.is_empty() {
+        println!(
+            "{} If the container keeps crashing, override its command:",
+            "[TIP]".yellow()
+        );
+        println!("  kdbg debug {} --copy -- sleep infinity", pod_name);
+    }
+    delete_pod(&copy_name, &ns);
+
+    result
+}
+
+/// Pass the container-level parts of a profile to `kubectl debug`: env via
+/// `--env`, securityContext (and resources, if `with_resources`) via a
+/// `--custom` file, whose path is returned for cleanup. Pod-level fields
+/// can't be changed in this mode and are reported as skipped.
+fn apply_profile(
+    cmd: &mut Command,
+    profile: &DebugProfile,
+    opts: &DebugOptions,
+    mode: &str,
+    with_resources: bool,
+) -> Result<Option<std::path::PathBuf>> {
+    let mut skipped = Vec::new();
+    if !profile.command.is_empty() {
+        skipped.push("command");
+    }
+    if !profile.node_selector.is_empty() {
+        skipped.push("nodeSelector");
+    }
+    if !profile.tolerations.is_empty() {
+        skipped.push("tolerations");
+    }
+    if profile.service_account.is_some() {
+        skipped.push("serviceAccount");
+    }
+    if profile.resources.is_some() && !with_resources {
+        skipped.push("resources");
+    }
+    if !skipped.is_empty() {
+        println!(
+            "{} Profile '{}': {} not supported in {} mode, skipped",
+            "[WARN]".yellow(),
+            opts.profile.as_deref().unwrap_or("?"),
+            skipped.join(", "),
+            mode
+        );
+    }
+
+    for (name, value) in &profile.env {
+        cmd.arg(format!("--env={}={}", name, value));
+    }
+
+    let mut custom = serde_json::Map::new();
+    if let Some(security_context) = &profile.security_context {
+        custom.insert("securityContext".to_string(), security_context.clone());
+    }
+    if let (Some(resources), true) = (&profile.resources, with_resources) {
+        custom.insert("resources".to_string(), resources.clone());
+    }
+    if custom.is_empty() {
+        return Ok(None);
+    }
+
+    fs::create_dir_all(get_kdbg_dir())?;
+    let path = get_kdbg_dir().join(format!(
+        "debug-custom-{}-{}.json",
+        std::process::id(),
+        timestamp()
+    ));
+    fs::write(&path, Value::Object(custom).to_string())?;
+    cmd.arg(format!("--custom={}", path.display()));
+
+    Ok(Some(path))
+}
+
+/// List kdbg-created pods in all namespaces and delete the expired ones:
+/// older than `older_than` if given, otherwise past their own TTL label
+pub fn gc_debug_pods(older_than: Option<&str>, dry_run: bool, quiet: bool) -> Result<()> {
+    let older_than = older_than
+        .map(|d| parse_duration(d).with_context(|| format!("Invalid duration '{}'", d)))
+        .transpose()?;
+    let default_ttl = parse_duration(&Config::load()?.debug.ttl).unwrap_or(3600);
+
+    let _ = fs::create_dir_all(get_kdbg_dir());
+    let _ = fs::write(get_kdbg_dir().join("last-gc"), timestamp().to_string());
+
     let output = Command::new("kubectl")
         .args([
-            "run",
-            &pod_name,
-            "--image",
-            image,
+            "get",
+            "pods",
+            "--all-namespaces",
+            "-l",
+            &format!("{}=kdbg", MANAGED_BY_LABEL),
+            "-o",
+            "json",
+        ])
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!("Failed to list debug pods");
+    }
+    let json: Value = serde_json::from_slice(&output.stdout)?;
+    let empty_vec = vec![];
+    let pods = json["items"].as_array().unwrap_or(&empty_vec);
+
+    if pods.is_empty() {
+        if !quiet {
+            println!("{} No kdbg-created pods found", "[INFO]".green());
+        }
+        return Ok(());
+    }
+
+    if !quiet {
+        println!("{}", "Debug Pods:".cyan().bold());
+        println!("{}", "-".repeat(100));
+        println!(
+            "{:<15} {:<45} {:<15} {:>6} {:>6} {:<10}",
+            "NAMESPACE", "NAME", "CREATOR", "AGE", "TTL", "STATUS"
+        );
+        println!("{}", "-".repeat(100));
+    }
+
+    let now = timestamp();
+    let mut expired = Vec::new();
+    for pod in pods {
+        let name = pod["metadata"]["name"].as_str().unwrap_or("unknown");
+        let ns = pod["metadata"]["namespace"].as_str().unwrap_or("default");
+        let labels = &pod["metadata"]["labels"];
+
+        let created = labels[CREATED_AT_LABEL]
+            .as_str()
+            .and_then(|t| t.parse::<u64>().ok())
+            .or_else(|| {
+                pod["metadata"]["creationTimestamp"]
+                    .as_str()
+                    .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                    .map(|t| t.timestamp().max(0) as u64)
+            })
+            .unwrap_or(now);
+        let age = now.saturating_sub(created);
+        let ttl = labels[TTL_LABEL]
+            .as_str()
+            .and_then(parse_duration)
+            .unwrap_or(default_ttl);
+        let limit = older_than.unwrap_or(ttl);
+        let is_expired = age >= limit;
+
+        if !quiet {
+            println!(
+                "{:<15} {:<45} {:<15} {:>6} {:>6} {:<10}",
+                ns.bright_black(),
+                name.cyan(),
+                labels[CREATED_BY_LABEL].as_str().unwrap_or("-"),
+                format_duration(age as i64),
+                format_duration(ttl as i64),
+                if is_expired {
+                    "expired".red()
+                } else {
+                    "active".green()
+                }
+            );
+        }
+        if is_expired {
+            expired.push((name, ns));
+        }
+    }
+
+    if dry_run {
+        if !quiet {
+            println!(
+                "\n{} {} expired pods would be deleted (dry run)",
+                "[INFO]".yellow(),
+                expired.len()
+            );
+        }
+        return Ok(());
+    }
+
+    for (name, ns) in &expired {
+        let _ = Command::new("kubectl")
+            .args(["delete", "pod", name, "-n", ns, "--wait=false"])
+            .stdout(Stdio::null())
+            .status();
+    }
+    if !quiet {
+        println!(
+            "\n{} Deleted {} expired debug pods",
+            "[INFO]".green(),
+            expired.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// When `auto_gc` is enabled, run `kdbg debug gc` in the background at most
+/// once per AUTO_GC_INTERVAL. Never fails the command that triggered it.
+pub fn auto_gc() {
+    let Ok(config) = Config::load() else {
+        return;
+    };
+    if !config.debug.auto_gc {
+        return;
+    }
+
+    let last = fs::read_to_string(get_kdbg_dir().join("last-gc"))
+        .ok()
+        .and_then(|t| t.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    if timestamp().saturating_sub(last) < AUTO_GC_INTERVAL {
+        return;
+    }
+
+    if let Ok(exe) = std::env::current_exe() {
+        let _ = Command::new(exe)
+            .args(["debug", "gc", "--quiet"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            // Own process group so Ctrl+C on the foreground command spares it
+            .process_group(0)
+            .spawn();
+    }
+}
+
+/// Labels recording who created a pod, when, and how long it may live
+fn kdbg_labels(ttl: u64) -> BTreeMap<&'static str, String> {
+    let user = std::env::var("USER").unwrap_or_else(|_| "unknown".to_string());
+    // Label values: alphanumerics, '-', '_', '.', at most 63 characters
+    let user: String = user
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .take(63)
+        .collect();
+    let user = user.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+
+    BTreeMap::from([
+        (MANAGED_BY_LABEL, "kdbg".to_string()),
+        (
+            CREATED_BY_LABEL,
+            if user.is_empty() { "unknown" } else { user }.to_string(),
+        ),
+        (CREATED_AT_LABEL, timestamp().to_string()),
+        (TTL_LABEL, format!("{}s", ttl)),
+    ])
+}
+
+fn create_pod(manifest: &Value) -> Result<()> {
+    let mut child = Command::new("kubectl")
+        .args(["create", "-f", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .context("Failed to open kubectl stdin")?
+        .write_all(manifest.to_string().as_bytes())?;
+
+    if !child.wait()?.success() {
+        anyhow::bail!("Failed to create debug pod");
+    }
+
+    Ok(())
+}
+
+/// Wait for the pod to become ready and open a shell in it
+fn wait_and_shell(pod: &str, ns: &str, container: Option<&str>, record: bool) -> Result<()> {
+    println!("{} Waiting for {} to be ready...", "[INFO]".cyan(), pod);
+    let ready = Command::new("kubectl")
+        .args([
+            "wait",
+            "--for=condition=Ready",
+            &format!("pod/{}", pod),
             "-n",
-            namespace,
-            "--restart=Never",
-            "--rm",
-            "-it",
-            "--",
-            "/bin/sh",
+            ns,
+            "--timeout=120s",
         ])
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
+        .stdout(Stdio::null())
         .status()?;
+    if !ready.success() {
+        anyhow::bail!("Debug pod {} did not become ready", pod);
+    }
 
-    if !output.success() {
-        anyhow::bail!("Failed to create debug pod");
+    println!(
+        "{} Pod will be deleted when you exit the shell",
+        "[INFO]".yellow()
+    );
+    println!("{}", "-".repeat(100));
+
+    let mut cmd = Command::new("kubectl");
+    cmd.args(["exec", "-it", pod, "-n", ns]);
+    if let Some(container) = container {
+        cmd.args(["-c", container]);
     }
+    cmd.args(["--", "/bin/sh"]);
 
+    // The shell's exit code is the user's business, not a failure
+    run_session(cmd, pod, ns, container, record)?;
     Ok(())
 }
+
+fn run_session(
+    mut cmd: Command,
+    pod: &str,
+    ns: &str,
+    container: Option<&str>,
+    record: bool,
+) -> Result<std::process::ExitStatus> {
+    if record {
+        let meta = SessionMeta {
+            kind: "debug".to_string(),
+            pod: pod.to_string(),
+            namespace: ns.to_string(),
+            container: container.map(str::to_string),
+        };
+        run_recorded(cmd, &meta)
+    } else {
+        Ok(cmd
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()?)
+    }
+}
+
+fn delete_pod(pod: &str, ns: &str) {
+    println!("{} Deleting {}", "[INFO]".cyan(), pod);
+    let _ = Command::new("kubectl")
+        .args(["delete", "pod", pod, "-n", ns, "--wait=false"])
+        .stdout(Stdio::null())
+        .status();
+}
+
+/// `<base>-<suffix>-<timestamp>`, trimmed to fit the 63 character name limit
+fn debug_name(base: &str, suffix: &str) -> String {
+    let tail = if suffix.is_empty() {
+        format!("-{}", timestamp())
+    } else {
+        format!("-{}-{}", suffix, timestamp())
+    };
+    let base: String = base.chars().take(63 - tail.len()).collect();
+    format!("{}{}", base.trim_end_matches(['-', '.']), tail)
+}
+
+fn timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
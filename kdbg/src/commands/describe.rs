@@ -1,9 +1,16 @@
-use crate::kubectl::find_pod;
+use crate::kubectl::{event_time, find_pod, get_pod_events, get_pod_json};
+use crate::utils::calculate_age;
 use anyhow::Result;
 use colored::*;
+use serde_json::Value;
 use std::process::Command;
 
-pub fn describe_pod(pod_pattern: &str, namespace: Option<String>) -> Result<()> {
+pub fn describe_pod(
+    pod_pattern: &str,
+    namespace: Option<String>,
+    summary: bool,
+    events: usize,
+) -> Result<()> {
     let (pod_name, ns) = find_pod(pod_pattern, namespace)?;
 
     println!(
@@ -14,6 +21,13 @@ pub fn describe_pod(pod_pattern: &str, namespace: Option<String>) -> Result<()>
     );
     println!("{}", "-".repeat(100));
 
+    if summary {
+        let pod = get_pod_json(&pod_name, &ns)?;
+        let pod_events = get_pod_events(&pod_name, &ns).unwrap_or_default();
+        print_summary(&pod, &pod_events, events);
+        return Ok(());
+    }
+
     let status = Command::new("kubectl")
         .args(["describe", "pod", &pod_name, "-n", &ns])
         .status()?;
@@ -24,3 +38,336 @@ pub fn describe_pod(pod_pattern: &str, namespace: Option<String>) -> Result<()>
 
     Ok(())
 }
+
+fn print_summary(pod: &Value, events: &[Value], max_events: usize) {
+    let status = &pod["status"];
+    let phase = status["phase"].as_str().unwrap_or("Unknown");
+
+    let ips: Vec<&str> = status["podIPs"]
+        .as_array()
+        .map(|ips| ips.iter().filter_map(|ip| ip["ip"].as_str()).collect())
+        .unwrap_or_default();
+
+    field("Status", phase_colored(phase).to_string());
+    field(
+        "Node",
+        format!(
+            "{} ({})",
+            pod["spec"]["nodeName"].as_str().unwrap_or("<none>"),
+            status["hostIP"].as_str().unwrap_or("-")
+        ),
+    );
+    field(
+        "Pod IPs",
+        if ips.is_empty() {
+            "<none>".to_string()
+        } else {
+            ips.join(", ")
+        },
+    );
+    field(
+        "QoS",
+        status["qosClass"].as_str().unwrap_or("-").to_string(),
+    );
+    field(
+        "Age",
+        pod["metadata"]["creationTimestamp"]
+            .as_str()
+            .map(calculate_age)
+            .unwrap_or_else(|| "unknown".to_string()),
+    );
+    if let Some(owner) = pod["metadata"]["ownerReferences"]
+        .as_array()
+        .and_then(|o| o.first())
+    {
+        field(
+            "Controlled By",
+            format!(
+                "{}/{}",
+                owner["kind"].as_str().unwrap_or("?"),
+                owner["name"].as_str().unwrap_or("?")
+            ),
+        );
+    }
+
+    section("Conditions");
+    if let Some(conditions) = status["conditions"].as_array() {
+        for condition in conditions {
+            let value = condition["status"].as_str().unwrap_or("Unknown");
+            let value_colored = match value {
+                "True" => value.green(),
+                "False" => value.red(),
+                _ => value.yellow(),
+            };
+            let since = condition["lastTransitionTime"]
+                .as_str()
+                .map(|t| format!("{} ago", calculate_age(t)))
+                .unwrap_or_default();
+            let reason = condition["reason"].as_str().unwrap_or("");
+
+            println!(
+                "  {:<28} {:<8} {:<12} {}",
+                condition["type"].as_str().unwrap_or("?"),
+                value_colored,
+                since.bright_black(),
+                reason.yellow()
+            );
+        }
+    }
+
+    let empty_vec = vec![];
+    let statuses: Vec<&Value> = ["initContainerStatuses", "containerStatuses"]
+        .iter()
+        .filter_map(|key| status[key].as_array())
+        .flatten()
+        .collect();
+
+    for (kind, key) in [
+        ("Init Container", "initContainers"),
+        ("Container", "containers"),
+    ] {
+        for container in pod["spec"][key].as_array().unwrap_or(&empty_vec) {
+            let name = container["name"].as_str().unwrap_or("unknown");
+            let container_status = statuses
+                .iter()
+                .find(|s| s["name"].as_str() == Some(name))
+                .copied()
+                .unwrap_or(&Value::Null);
+            print_container(kind, container, container_status);
+        }
+    }
+
+    section(&format!("Warning Events (last {})", max_events));
+    let warnings: Vec<&Value> = events
+        .iter()
+        .filter(|e| e["type"].as_str() == Some("Warning"))
+        .collect();
+
+    if warnings.is_empty() {
+        println!("  {}", "(none)".bright_black());
+    }
+
+    for event in warnings
+        .iter()
+        .skip(warnings.len().saturating_sub(max_events))
+    {
+        let reason = event["reason"].as_str().unwrap_or("");
+        let count = event["count"].as_u64().unwrap_or(1);
+        let age = calculate_age(event_time(event));
+        let message = event["message"].as_str().unwrap_or("").trim();
+
+        println!(
+            "  {:<6} {:<22} {:<6} {}",
+            age.bright_black(),
+            severity_colored(reason),
+            format!("x{}", count),
+            message
+        );
+    }
+}
+
+fn print_container(kind: &str, container: &Value, status: &Value) {
+    let name = container["name"].as_str().unwrap_or("unknown");
+    section(&format!("{}: {}", kind, name));
+
+    field(
+        "Image",
+        container["image"].as_str().unwrap_or("-").to_string(),
+    );
+
+    let ready = status["ready"].as_bool().unwrap_or(false);
+    let restarts = status["restartCount"].as_u64().unwrap_or(0);
+    field("State", describe_state(&status["state"]));
+    field(
+        "Ready",
+        format!(
+            "{}  (restarts: {})",
+            if ready { "true".green() } else { "false".red() },
+            if restarts > 0 {
+                restarts.to_string().yellow()
+            } else {
+                restarts.to_string().normal()
+            }
+        ),
+    );
+
+    let last = &status["lastState"]["terminated"];
+    if last.is_object() {
+        field(
+            "Last Termination",
+            format!(
+                "{} (exit code {}) {}",
+                last["reason"].as_str().unwrap_or("Unknown").red(),
+                last["exitCode"].as_i64().unwrap_or(-1),
+                last["finishedAt"]
+                    .as_str()
+                    .map(|t| format!("{} ago", calculate_age(t)))
+                    .unwrap_or_default()
+                    .bright_black()
+            ),
+        );
+    }
+
+    let resources = &container["resources"];
+    field(
+        "Requests",
+        format_resources(&resources["requests"]).unwrap_or_else(|| "<none>".to_string()),
+    );
+    field(
+        "Limits",
+        format_resources(&resources["limits"]).unwrap_or_else(|| "<none>".to_string()),
+    );
+
+    for (label, key) in [
+        ("Liveness", "livenessProbe"),
+        ("Readiness", "readinessProbe"),
+        ("Startup", "startupProbe"),
+    ] {
+        if container[key].is_object() {
+            field(label, format_probe(&container[key]));
+        }
+    }
+
+    if let Some(mounts) = container["volumeMounts"].as_array() {
+        println!("  Mounts:");
+        for mount in mounts {
+            println!(
+                "    {} from {}{}",
+                mount["mountPath"].as_str().unwrap_or("?"),
+                mount["name"].as_str().unwrap_or("?").cyan(),
+                if mount["readOnly"].as_bool().unwrap_or(false) {
+                    " (ro)"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+}
+
+fn describe_state(state: &Value) -> String {
+    if let Some(running) = state["running"].as_object() {
+        let since = running
+            .get("startedAt")
+            .and_then(|t| t.as_str())
+            .map(|t| format!(" for {}", calculate_age(t)))
+            .unwrap_or_default();
+        format!("{}{}", "Running".green(), since)
+    } else if let Some(waiting) = state["waiting"].as_object() {
+        let reason = waiting
+            .get("reason")
+            .and_then(|r| r.as_str())
+            .unwrap_or("Waiting");
+        let message = waiting
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(|m| format!(": {}", m))
+            .unwrap_or_default();
+        format!("{}{}", reason.yellow(), message)
+    } else if let Some(terminated) = state["terminated"].as_object() {
+        let reason = terminated
+            .get("reason")
+            .and_then(|r| r.as_str())
+            .unwrap_or("Terminated");
+        let code = terminated
+            .get("exitCode")
+            .and_then(|c| c.as_i64())
+            .unwrap_or(-1);
+        let reason = if code == 0 {
+            reason.blue()
+        } else {
+            reason.red()
+        };
+        format!("{} (exit code {})", reason, code)
+    } else {
+        "Unknown".bright_black().to_string()
+    }
+}
+
+fn format_resources(resources: &Value) -> Option<String> {
+    let map = resources.as_object().filter(|m| !m.is_empty())?;
+    Some(
+        map.iter()
+            .map(|(k, v)| format!("{}={}", k, v.as_str().unwrap_or("?")))
+            .collect::<Vec<_>>()
+            .join(", "),
+    )
+}
+
+fn format_probe(probe: &Value) -> String {
+    let action = if let Some(http) = probe["httpGet"].as_object() {
+        format!(
+            "http-get {}://:{}{}",
+            http.get("scheme")
+                .and_then(|s| s.as_str())
+                .unwrap_or("HTTP")
+                .to_lowercase(),
+            http.get("port").map(port_str).unwrap_or_default(),
+            http.get("path").and_then(|p| p.as_str()).unwrap_or("/")
+        )
+    } else if let Some(tcp) = probe["tcpSocket"].as_object() {
+        format!(
+            "tcp-socket :{}",
+            tcp.get("port").map(port_str).unwrap_or_default()
+        )
+    } else if let Some(command) = probe["exec"]["command"].as_array() {
+        let argv: Vec<&str> = command.iter().filter_map(|c| c.as_str()).collect();
+        format!("exec [{}]", argv.join(" "))
+    } else if probe["grpc"].is_object() {
+        format!("grpc :{}", port_str(&probe["grpc"]["port"]))
+    } else {
+        "unknown".to_string()
+    };
+
+    format!(
+        "{} {}",
+        action,
+        format!(
+            "delay={}s timeout={}s period={}s #failure={}",
+            probe["initialDelaySeconds"].as_u64().unwrap_or(0),
+            probe["timeoutSeconds"].as_u64().unwrap_or(1),
+            probe["periodSeconds"].as_u64().unwrap_or(10),
+            probe["failureThreshold"].as_u64().unwrap_or(3)
+        )
+        .bright_black()
+    )
+}
+
+fn port_str(port: &Value) -> String {
+    port.as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| port.to_string())
+}
+
+fn phase_colored(phase: &str) -> ColoredString {
+    match phase {
+        "Running" => phase.green(),
+        "Pending" => phase.yellow(),
+        "Failed" => phase.red(),
+        "Succeeded" => phase.blue(),
+        _ => phase.normal(),
+    }
+}
+
+/// Red for reasons that mean something is broken, yellow for the rest
+fn severity_colored(reason: &str) -> ColoredString {
+    let critical = reason.starts_with("Failed")
+        || matches!(
+            reason,
+            "BackOff" | "OOMKilling" | "Evicted" | "Unhealthy" | "ErrImagePull" | "Killing"
+        );
+    if critical {
+        reason.red()
+    } else {
+        reason.yellow()
+    }
+}
+
+fn section(title: &str) {
+    println!();
+    println!("{}", title.cyan().bold());
+}
+
+fn field(label: &str, value: String) {
+    println!("  {:<18} {}", format!("{}:", label), value);
+}
@@ -0,0 +1,79 @@
+use crate::diagnosis::{containers_needing_logs, diagnose, PodContext, Severity};
+use crate::kubectl::{find_pod, get_pod_events, get_pod_json};
+use anyhow::Result;
+use colored::*;
+use std::collections::HashMap;
+use std::process::Command;
+
+pub fn diagnose_pod(pod_pattern: &str, namespace: Option<String>) -> Result<()> {
+    let (pod_name, ns) = find_pod(pod_pattern, namespace)?;
+
+    println!(
+        "{} Diagnosing pod: {} (namespace: {})",
+        "[INFO]".cyan(),
+        pod_name.bold(),
+        ns.bright_black()
+    );
+    println!("{}", "-".repeat(100));
+
+    let pod = get_pod_json(&pod_name, &ns)?;
+    let events = get_pod_events(&pod_name, &ns).unwrap_or_default();
+
+    let mut previous_logs = HashMap::new();
+    for container in containers_needing_logs(&pod) {
+        let output = Command::new("kubectl")
+            .args([
+                "logs",
+                &pod_name,
+                "-n",
+                &ns,
+                "-c",
+                &container,
+                "--previous",
+                "--tail",
+                "20",
+            ])
+            .output()?;
+        if output.status.success() {
+            previous_logs.insert(
+                container,
+                String::from_utf8_lossy(&output.stdout).to_string(),
+            );
+        }
+    }
+
+    let ctx = PodContext {
+        pod: &pod,
+        events: &events,
+        previous_logs: &previous_logs,
+        now: chrono::Utc::now(),
+    };
+    let findings = diagnose(&ctx);
+
+    if findings.is_empty() {
+        println!(
+            "{} No known problems detected (phase: {})",
+            "[OK]".green(),
+            pod["status"]["phase"].as_str().unwrap_or("Unknown")
+        );
+        return Ok(());
+    }
+
+    for finding in &findings {
+        let label = match finding.severity {
+            Severity::Critical => "[CRITICAL]".red().bold(),
+            Severity::Warning => "[WARNING]".yellow().bold(),
+            Severity::Info => "[INFO]".cyan().bold(),
+        };
+        println!("{} {}", label, finding.title.bold());
+        for detail in &finding.details {
+            println!("    {}", detail.bright_black());
+        }
+        println!("    {} {}", "→".cyan(), finding.suggestion.cyan());
+        println!();
+    }
+
+    println!("Total: {} findings", findings.len());
+
+    Ok(())
+}
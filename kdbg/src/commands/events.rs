@@ -1,35 +1,289 @@
-use crate::kubectl::find_pod;
-use anyhow::Result;
+use crate::kubectl::{event_first_time, event_time, events_scope, find_pod, get_events};
+use crate::utils::{calculate_age, parse_duration};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
 use colored::*;
-use std::process::Command;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::process::{Command, Stdio};
 
-pub fn show_events(pod_pattern: &str, namespace: Option<String>) -> Result<()> {
-    let (pod_name, ns) = find_pod(pod_pattern, namespace)?;
+/// Which events to show
+#[derive(Default)]
+pub struct EventFilter {
+    /// "Normal" or "Warning"
+    pub event_type: Option<String>,
+    /// Kind of the involved object, e.g. "Pod" or "Node"
+    pub kind: Option<String>,
+    /// Substring of the reason, e.g. "BackOff"
+    pub reason: Option<String>,
+    /// Only events last seen within this window, e.g. "30m"
+    pub since: Option<String>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &Value, since: Option<DateTime<Utc>>) -> bool {
+        let recent = match since {
+            Some(since) => DateTime::parse_from_rfc3339(event_time(event))
+                .is_ok_and(|t| t.with_timezone(&Utc) >= since),
+            None => true,
+        };
+
+        recent
+            && field_matches(&self.event_type, event["type"].as_str(), false)
+            && field_matches(&self.kind, event["involvedObject"]["kind"].as_str(), false)
+            && field_matches(&self.reason, event["reason"].as_str(), true)
+    }
+}
+
+/// Case-insensitive comparison, or substring match when `partial`; no
+/// filter matches everything
+fn field_matches(wanted: &Option<String>, actual: Option<&str>, partial: bool) -> bool {
+    let (Some(wanted), Some(actual)) = (wanted, actual) else {
+        return wanted.is_none();
+    };
+    let (wanted, actual) = (wanted.to_lowercase(), actual.to_lowercase());
+    if partial {
+        actual.contains(&wanted)
+    } else {
+        actual == wanted
+    }
+}
+
+/// Show events for one pod, a namespace, or the whole cluster (no namespace)
+pub fn show_events(
+    pod_pattern: Option<&str>,
+    namespace: Option<String>,
+    filter: &EventFilter,
+    aggregate: bool,
+    watch: bool,
+) -> Result<()> {
+    let since = match &filter.since {
+        Some(since) => {
+            let seconds =
+                parse_duration(since).with_context(|| format!("Invalid duration '{}'", since))?;
+            // Further back than chrono can go means no cutoff at all
+            chrono::TimeDelta::try_seconds(seconds as i64)
+                .and_then(|d| Utc::now().checked_sub_signed(d))
+        }
+        None => None,
+    };
+
+    let (namespace, object) = match pod_pattern {
+        Some(pattern) => {
+            let (pod_name, ns) = find_pod(pattern, namespace)?;
+            println!(
+                "{} Events for pod: {} (namespace: {})",
+                "[INFO]".cyan(),
+                pod_name.bold(),
+                ns.bright_black()
+            );
+            (Some(ns), Some(pod_name))
+        }
+        None => {
+            println!(
+                "{} Events in {}",
+                "[INFO]".cyan(),
+                match &namespace {
+                    Some(ns) => format!("namespace {}", ns.bold()),
+                    None => "all namespaces".bold().to_string(),
+                }
+            );
+            (namespace, None)
+        }
+    };
+    let all_namespaces = namespace.is_none();
+
+    let events: Vec<Value> = get_events(namespace.as_deref(), object.as_deref())?
+        .into_iter()
+        .filter(|e| filter.matches(e, since))
+        .collect();
 
+    if aggregate {
+        print_aggregated(&events, all_namespaces);
+    } else {
+        print_events(&events, all_namespaces);
+    }
+
+    if watch {
+        watch_events(
+            namespace.as_deref(),
+            object.as_deref(),
+            filter,
+            all_namespaces,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn print_events(events: &[Value], all_namespaces: bool) {
+    println!("{}", "-".repeat(100));
     println!(
-        "{} Events for pod: {} (namespace: {})",
-        "[INFO]".cyan(),
-        pod_name.bold(),
-        ns.bright_black()
+        "{:<10} {:<8} {:<22} {:<40} MESSAGE",
+        "LAST SEEN", "TYPE", "REASON", "OBJECT"
     );
     println!("{}", "-".repeat(100));
 
-    let status = Command::new("kubectl")
-        .args([
-            "get",
-            "events",
-            "-n",
-            &ns,
-            "--field-selector",
-            &format!("involvedObject.name={}", pod_name),
-            "--sort-by",
-            ".lastTimestamp",
-        ])
-        .status()?;
+    for event in events {
+        let count = event_count(event);
+        let last_seen = if count > 1 {
+            format!("{} (x{})", calculate_age(event_time(event)), count)
+        } else {
+            calculate_age(event_time(event))
+        };
+        println!(
+            "{:<10} {:<8} {:<22} {:<40} {}",
+            last_seen,
+            colored_type(event),
+            event["reason"].as_str().unwrap_or("-"),
+            object_name(event, all_namespaces).cyan(),
+            event["message"].as_str().unwrap_or("").trim()
+        );
+    }
 
-    if !status.success() {
-        anyhow::bail!("Failed to get events");
+    println!("\nTotal: {} events", events.len());
+}
+
+/// Group repeated events by object and reason, most frequent first
+fn print_aggregated(events: &[Value], all_namespaces: bool) {
+    struct Group<'a> {
+        count: u64,
+        first: &'a str,
+        last: &'a str,
+        warning: bool,
+        message: &'a str,
+    }
+
+    let mut groups: HashMap<(String, &str), Group> = HashMap::new();
+    for event in events {
+        let key = (
+            object_name(event, all_namespaces),
+            event["reason"].as_str().unwrap_or("-"),
+        );
+        let group = groups.entry(key).or_insert(Group {
+            count: 0,
+            first: event_first_time(event),
+            last: event_time(event),
+            warning: false,
+            message: "",
+        });
+        group.count += event_count(event);
+        group.first = group.first.min(event_first_time(event));
+        if event_time(event) >= group.last {
+            group.last = event_time(event);
+            group.message = event["message"].as_str().unwrap_or("").trim();
+        }
+        group.warning |= event["type"].as_str() == Some("Warning");
+    }
+
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(b.1.last.cmp(a.1.last)));
+
+    println!("{}", "-".repeat(100));
+    println!(
+        "{:>6} {:<22} {:<40} {:>10} {:>10}",
+        "COUNT", "REASON", "OBJECT", "FIRST SEEN", "LAST SEEN"
+    );
+    println!("{}", "-".repeat(100));
+
+    for ((object, reason), group) in &groups {
+        let reason = if group.warning {
+            reason.yellow()
+        } else {
+            reason.normal()
+        };
+        println!(
+            "{:>6} {:<22} {:<40} {:>10} {:>10}",
+            group.count.to_string().bold(),
+            reason,
+            object.cyan(),
+            calculate_age(group.first),
+            calculate_age(group.last)
+        );
+        if !group.message.is_empty() {
+            println!("       {}", group.message.bright_black());
+        }
+    }
+
+    println!(
+        "\nTotal: {} events in {} groups",
+        groups.iter().map(|(_, g)| g.count).sum::<u64>(),
+        groups.len()
+    );
+}
+
+/// Stream new events as they arrive until interrupted
+fn watch_events(
+    namespace: Option<&str>,
+    object: Option<&str>,
+    filter: &EventFilter,
+    all_namespaces: bool,
+) -> Result<()> {
+    println!(
+        "\n{} Watching for new events (press Ctrl+C to stop)...",
+        "[INFO]".cyan()
+    );
+
+    let mut child = Command::new("kubectl")
+        .args(["get", "events", "--watch-only", "-o", "json"])
+        .args(events_scope(namespace, object))
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = child
+        .stdout
+        .take()
+        .context("Failed to read kubectl output")?;
+
+    // kubectl writes one JSON document per event, back to back
+    for event in serde_json::Deserializer::from_reader(BufReader::new(stdout)).into_iter::<Value>()
+    {
+        let event = event?;
+        if !filter.matches(&event, None) {
+            continue;
+        }
+        println!(
+            "{} {:<8} {:<22} {:<40} {}",
+            Local::now().format("%H:%M:%S").to_string().bright_black(),
+            colored_type(&event),
+            event["reason"].as_str().unwrap_or("-"),
+            object_name(&event, all_namespaces).cyan(),
+            event["message"].as_str().unwrap_or("").trim()
+        );
+    }
+
+    if !child.wait()?.success() {
+        anyhow::bail!("Event watch failed");
     }
 
     Ok(())
 }
+
+/// "Pod/api-1", prefixed with the namespace when listing all namespaces
+fn object_name(event: &Value, all_namespaces: bool) -> String {
+    let object = &event["involvedObject"];
+    let name = format!(
+        "{}/{}",
+        object["kind"].as_str().unwrap_or("?"),
+        object["name"].as_str().unwrap_or("?")
+    );
+    match object["namespace"].as_str() {
+        Some(ns) if all_namespaces => format!("{}/{}", ns, name),
+        _ => name,
+    }
+}
+
+fn event_count(event: &Value) -> u64 {
+    event["count"]
+        .as_u64()
+        .or_else(|| event["series"]["count"].as_u64())
+        .unwrap_or(1)
+}
+
+fn colored_type(event: &Value) -> ColoredString {
+    match event["type"].as_str().unwrap_or("-") {
+        "Warning" => "Warning".yellow(),
+        "Normal" => "Normal".green(),
+        other => other.normal(),
+    }
+}
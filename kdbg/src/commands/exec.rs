@@ -1,27 +1,330 @@
-use crate::kubectl::find_pod;
+use crate::kubectl::{find_container, find_pod, find_pods, match_container};
+use crate::recorder::{run_recorded, SessionMeta};
+use crate::utils::pod_color;
 use anyhow::Result;
 use colored::*;
-use std::process::Command;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, IsTerminal};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
-pub fn exec_pod(pod_pattern: &str, namespace: Option<String>, command: &str) -> Result<()> {
+/// Options for running one command across many pods
+pub struct FanOut {
+    /// Print each pod's output as one block when it finishes
+    pub grouped: bool,
+    /// Maximum number of pods to exec into at once
+    pub parallel: usize,
+    /// Kill the exec if a pod takes longer than this
+    pub timeout: Duration,
+}
+
+enum Outcome {
+    Exited(i32),
+    TimedOut,
+    Failed(String),
+}
+
+enum Message {
+    Line(usize, String),
+    Done(usize, Outcome, Duration),
+}
+
+/// Run a command in a pod and return its exit code
+pub fn exec_pod(
+    pod_pattern: &str,
+    namespace: Option<String>,
+    container: Option<&str>,
+    command: Option<&str>,
+    args: &[String],
+    record: bool,
+) -> Result<i32> {
     let (pod_name, ns) = find_pod(pod_pattern, namespace)?;
+    let container = container
+        .map(|c| find_container(&pod_name, &ns, c))
+        .transpose()?;
+    let argv = command_argv(command, args);
+
+    // Only attach a TTY when both ends are terminals, so piped stdin and
+    // captured stdout work from scripts
+    let interactive = std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+
+    if interactive {
+        println!(
+            "{} Executing in pod: {} (namespace: {})",
+            "[INFO]".cyan(),
+            pod_name.bold(),
+            ns.bright_black()
+        );
+        println!("{} Command: {}", "[INFO]".cyan(), argv.join(" ").yellow());
+        println!("{}", "-".repeat(100));
+    }
+
+    let mut cmd = Command::new("kubectl");
+    cmd.args(["exec", if interactive { "-it" } else { "-i" }])
+        .args([&pod_name, "-n", &ns]);
+    if let Some(container) = &container {
+        cmd.args(["-c", container]);
+    }
+    cmd.arg("--").args(&argv);
+
+    let status = if record {
+        let meta = SessionMeta {
+            kind: "exec".to_string(),
+            pod: pod_name,
+            namespace: ns,
+            container,
+        };
+        run_recorded(cmd, &meta)?
+    } else {
+        cmd.status()?
+    };
+
+    // kubectl exits with the remote command's exit code
+    match status.code() {
+        Some(code) => Ok(code),
+        None => anyhow::bail!("Failed to exec into pod"),
+    }
+}
+
+/// Work out the remote argv: trailing args are used as-is, a `--command`
+/// string with spaces runs through `sh -c`, and the default is `/bin/sh`
+pub fn command_argv(command: Option<&str>, args: &[String]) -> Vec<String> {
+    if !args.is_empty() {
+        return args.to_vec();
+    }
+
+    match command {
+        Some(cmd) if cmd.contains(char::is_whitespace) => {
+            vec!["/bin/sh".to_string(), "-c".to_string(), cmd.to_string()]
+        }
+        Some(cmd) => vec![cmd.to_string()],
+        None => vec!["/bin/sh".to_string()],
+    }
+}
+
+/// Run the same command in every pod matching a pattern or selector and
+/// return a non-zero exit code if any pod failed
+pub fn exec_all(
+    pod_pattern: &str,
+    namespace: Option<String>,
+    selector: Option<&str>,
+    container: Option<&str>,
+    argv: Vec<String>,
+    fan_out: FanOut,
+) -> Result<i32> {
+    let matches = find_pods(pod_pattern, namespace, selector)?;
+
+    // Resolve the container per pod, as a single exec would, from the JSON
+    // already listed; a pod without a match fails on its own
+    let containers: Vec<Result<Option<String>, String>> = matches
+        .iter()
+        .map(|pod| {
+            container
+                .map(|c| match_container(pod, c))
+                .transpose()
+                .map_err(|err| err.to_string())
+        })
+        .collect();
+
+    let pods: Vec<(String, String)> = matches
+        .iter()
+        .map(|pod| {
+            (
+                pod["metadata"]["name"]
+                    .as_str()
+                    .unwrap_or("unknown")
+                    .to_string(),
+                pod["metadata"]["namespace"]
+                    .as_str()
+                    .unwrap_or("default")
+                    .to_string(),
+            )
+        })
+        .collect();
 
     println!(
-        "{} Executing in pod: {} (namespace: {})",
+        "{} Running {} in {} pods (parallel: {}, timeout: {}s):",
         "[INFO]".cyan(),
-        pod_name.bold(),
-        ns.bright_black()
+        argv.join(" ").yellow(),
+        pods.len(),
+        fan_out.parallel,
+        fan_out.timeout.as_secs()
     );
-    println!("{} Command: {}", "[INFO]".cyan(), command.yellow());
+    for (i, (name, _)) in pods.iter().enumerate() {
+        println!("  {} {}", "●".color(pod_color(i)), name.color(pod_color(i)));
+    }
     println!("{}", "-".repeat(100));
 
-    let status = Command::new("kubectl")
-        .args(["exec", "-it", &pod_name, "-n", &ns, "--", command])
-        .status()?;
+    let queue = Arc::new(Mutex::new((0..pods.len()).collect::<VecDeque<_>>()));
+    let pods = Arc::new(pods);
+    let argv = Arc::new(argv);
+    let containers = Arc::new(containers);
+    let (tx, rx) = mpsc::channel();
+
+    for _ in 0..fan_out.parallel.clamp(1, pods.len()) {
+        let queue = Arc::clone(&queue);
+        let pods = Arc::clone(&pods);
+        let argv = Arc::clone(&argv);
+        let containers = Arc::clone(&containers);
+        let tx = tx.clone();
+        let timeout = fan_out.timeout;
+
+        thread::spawn(move || loop {
+            let next = queue.lock().unwrap().pop_front();
+            let Some(idx) = next else { break };
+
+            let (name, ns) = &pods[idx];
+            let started = Instant::now();
+            let outcome = match &containers[idx] {
+                Ok(resolved) => run_in_pod(idx, name, ns, resolved.as_deref(), &argv, timeout, &tx),
+                Err(err) => Outcome::Failed(err.clone()),
+            };
+            let _ = tx.send(Message::Done(idx, outcome, started.elapsed()));
+        });
+    }
+
+    drop(tx);
+
+    let mut buffered: Vec<Vec<String>> = vec![Vec::new(); pods.len()];
+    let mut results: Vec<Option<(Outcome, Duration)>> = (0..pods.len()).map(|_| None).collect();
+
+    for message in rx {
+        match message {
+            Message::Line(idx, line) => {
+                if fan_out.grouped {
+                    buffered[idx].push(line);
+                } else {
+                    let prefix = format!("[{}]", pods[idx].0).color(pod_color(idx));
+                    println!("{} {}", prefix, line);
+                }
+            }
+            Message::Done(idx, outcome, elapsed) => {
+                if fan_out.grouped {
+                    let color = pod_color(idx);
+                    println!("{}", format!("==> {} <==", pods[idx].0).color(color).bold());
+                    for line in buffered[idx].drain(..) {
+                        println!("{}", line);
+                    }
+                    println!();
+                }
+                results[idx] = Some((outcome, elapsed));
+            }
+        }
+    }
+
+    println!("{}", "-".repeat(100));
+    println!(
+        "{:<50} {:<20} {:<10} {}",
+        "POD".bold(),
+        "NAMESPACE".bold(),
+        "EXIT".bold(),
+        "TIME".bold()
+    );
+    println!("{}", "-".repeat(100).bright_black());
+
+    let mut failed = 0;
+    for (i, (name, ns)) in pods.iter().enumerate() {
+        let (exit, elapsed) = match &results[i] {
+            Some((Outcome::Exited(0), elapsed)) => ("0".green(), Some(*elapsed)),
+            Some((Outcome::Exited(code), elapsed)) => (code.to_string().red(), Some(*elapsed)),
+            Some((Outcome::TimedOut, elapsed)) => ("timeout".red(), Some(*elapsed)),
+            Some((Outcome::Failed(err), elapsed)) => {
+                eprintln!("{} {}: {}", "[ERROR]".red(), name, err);
+                ("error".red(), Some(*elapsed))
+            }
+            None => ("error".red(), None),
+        };
+        if !matches!(results[i], Some((Outcome::Exited(0), _))) {
+            failed += 1;
+        }
+
+        println!(
+            "{:<50} {:<20} {:<10} {}",
+            name.color(pod_color(i)),
+            ns.bright_black(),
+            exit,
+            elapsed
+                .map(|d| format!("{:.1}s", d.as_secs_f64()))
+                .unwrap_or_else(|| "-".to_string())
+        );
+    }
+
+    println!(
+        "\nTotal: {} pods, {} succeeded, {} failed",
+        pods.len(),
+        (pods.len() - failed).to_string().green(),
+        failed.to_string().red()
+    );
+
+    Ok(if failed > 0 { 1 } else { 0 })
+}
+
+fn run_in_pod(
+    idx: usize,
+    name: &str,
+    ns: &str,
+    container: Option<&str>,
+    argv: &[String],
+    timeout: Duration,
+    tx: &mpsc::Sender<Message>,
+) -> Outcome {
+    let mut cmd = Command::new("kubectl");
+    cmd.args(["exec", name, "-n", ns]);
+    if let Some(container) = container {
+        cmd.args(["-c", container]);
+    }
+    cmd.arg("--")
+        .args(argv)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+
+    let readers: Vec<_> = [
+        child
+            .stdout
+            .take()
+            .map(|s| Box::new(s) as Box<dyn std::io::Read + Send>),
+        child
+            .stderr
+            .take()
+            .map(|s| Box::new(s) as Box<dyn std::io::Read + Send>),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|stream| {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                let _ = tx.send(Message::Line(idx, line));
+            }
+        })
+    })
+    .collect();
+
+    let deadline = Instant::now() + timeout;
+    let outcome = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Outcome::Exited(status.code().unwrap_or(1)),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                break Outcome::TimedOut;
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(100)),
+            Err(e) => break Outcome::Failed(e.to_string()),
+        }
+    };
 
-    if !status.success() {
-        anyhow::bail!("Failed to exec into pod");
+    for reader in readers {
+        let _ = reader.join();
     }
 
-    Ok(())
+    outcome
 }
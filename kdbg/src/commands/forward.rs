@@ -1,40 +1,760 @@
-use crate::kubectl::find_pod;
-use anyhow::Result;
+use crate::config::{Config, ForwardSpec};
+use crate::forwards::{
+    get_forwards_dir, record_connection, record_started, ForwardState, FORWARD_ID_ENV,
+};
+use crate::kubectl::{find_pod, find_pods, get_pod_json};
+use crate::utils::calculate_age;
+use anyhow::{Context, Result};
 use colored::*;
-use std::process::Command;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::net::TcpListener;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 
+/// How often the forwarded pod is checked while the tunnel is up
+const POD_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// A connection that stayed up this long resets the backoff
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+
+/// What to forward to: a pod pattern, or the pods behind a service/deployment
+enum Target {
+    Pod(String),
+    Service(String),
+    Deployment(String),
+}
+
+impl Target {
+    fn parse(target: &str) -> Self {
+        match target.split_once('/') {
+            Some(("svc" | "service" | "services", name)) => Target::Service(name.to_string()),
+            Some(("deploy" | "deployment" | "deployments", name)) => {
+                Target::Deployment(name.to_string())
+            }
+            Some(("pod" | "pods" | "po", name)) => Target::Pod(name.to_string()),
+            _ => Target::Pod(target.to_string()),
+        }
+    }
+}
+
+/// Local side of a port mapping
+#[derive(Clone, Copy)]
+enum LocalPort {
+    Fixed(u16),
+    SameAsRemote,
+    Auto,
+}
+
+/// One `LOCAL:REMOTE` mapping; the remote side may be a named port
+struct PortSpec {
+    local: LocalPort,
+    remote: String,
+}
+
+impl PortSpec {
+    fn parse(spec: &str, auto: bool) -> Result<Self> {
+        let (local, remote) = match spec.split_once(':') {
+            Some(("", remote)) => (LocalPort::Auto, remote),
+            Some((local, remote)) => (
+                LocalPort::Fixed(
+                    local
+                        .parse()
+                        .with_context(|| format!("Invalid local port in '{}'", spec))?,
+                ),
+                remote,
+            ),
+            None if auto => (LocalPort::Auto, spec),
+            None => (LocalPort::SameAsRemote, spec),
+        };
+
+        if remote.is_empty() {
+            anyhow::bail!("Missing remote port in '{}'", spec);
+        }
+
+        Ok(Self {
+            local,
+            remote: remote.to_string(),
+        })
+    }
+
+    /// Parse all mappings given on the command line
+    fn parse_all(ports: &[String], auto: bool) -> Result<Vec<Self>> {
+        // `forward api 8080 80` used to mean local 8080 to remote 80; don't
+        // quietly turn that into two same-port mappings
+        if let [local, remote] = ports {
+            let numeric = |p: &str| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit());
+            if !auto && numeric(local) && numeric(remote) {
+                anyhow::bail!(
+                    "Ambiguous ports '{0} {1}': use {0}:{1} to map local {0} to remote {1}, \
+                     or {0}:{0} {1}:{1} for two ports",
+                    local,
+                    remote
+                );
+            }
+        }
+
+        ports.iter().map(|p| Self::parse(p, auto)).collect()
+    }
+}
+
+/// A resolved forwarding target: the pod to connect to and its mappings
+struct Resolved {
+    pod: String,
+    namespace: String,
+    ports: Vec<(u16, u16)>,
+}
+
+/// Forward one or more ports to a pod, service or deployment, re-resolving a
+/// fresh pod and reconnecting whenever the connection drops
 pub fn port_forward(
-    pod_pattern: &str,
-    local_port: u16,
-    pod_port: u16,
+    target: &str,
+    ports: &[String],
     namespace: Option<String>,
+    auto: bool,
 ) -> Result<()> {
-    let (pod_name, ns) = find_pod(pod_pattern, namespace)?;
+    let target = Target::parse(target);
+    let specs = PortSpec::parse_all(ports, auto)?;
+
+    // Local ports are picked once so they stay stable across reconnects
+    let mut locals: Vec<Option<u16>> = vec![None; specs.len()];
+    let mut backoff = Duration::from_secs(1);
+    let mut namespace = namespace;
+    let mut first = true;
+    record_started();
+
+    loop {
+        let resolved = match resolve(&target, namespace.clone(), &specs, &mut locals, first) {
+            // Stay in the namespace we first connected to
+            Ok(resolved) => {
+                namespace = Some(resolved.namespace.clone());
+                resolved
+            }
+            // Nothing has worked yet, so this is a usage error rather than a blip
+            Err(e) if first => return Err(e),
+            Err(e) => {
+                log_event(
+                    "[WARN]".yellow(),
+                    &format!("{:#}, retrying in {:?}", e, backoff),
+                );
+                thread::sleep(backoff);
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                continue;
+            }
+        };
+
+        let mappings: Vec<String> = resolved
+            .ports
+            .iter()
+            .map(|(local, remote)| format!("{}:{}", local, remote))
+            .collect();
+
+        if first {
+            println!(
+                "{} Port forwarding to {} (namespace: {})",
+                "[INFO]".cyan(),
+                resolved.pod.bold(),
+                resolved.namespace.bright_black()
+            );
+            for (local, remote) in &resolved.ports {
+                println!("  localhost:{} -> {}", local.to_string().green(), remote);
+            }
+            println!("{} Press Ctrl+C to stop", "[INFO]".yellow());
+            println!("{}", "-".repeat(100));
+        } else {
+            log_event(
+                "[INFO]".cyan(),
+                &format!("Reconnecting to {} ({})", resolved.pod, mappings.join(", ")),
+            );
+        }
+        let initial = first;
+        first = false;
+
+        let started = Instant::now();
+        let reason = run_tunnel(&resolved, &mappings, !initial)?;
 
+        // A first tunnel that dies straight away (port not exposed, RBAC,
+        // wrong remote port) won't fix itself by retrying
+        if initial && started.elapsed() < STABLE_AFTER {
+            anyhow::bail!("Port forward to {} failed: {}", resolved.pod, reason);
+        }
+        if started.elapsed() >= STABLE_AFTER {
+            backoff = Duration::from_secs(1);
+        }
+        log_event(
+            "[WARN]".yellow(),
+            &format!("{}, reconnecting in {:?}", reason, backoff),
+        );
+        thread::sleep(backoff);
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Start a forward in the background, detached from the terminal. Its state
+/// and log live under ~/.kdbg/forwards.
+pub fn start_forward(spec: &ForwardSpec, profile: Option<&str>) -> Result<()> {
+    fs::create_dir_all(get_forwards_dir())?;
+
+    let mut state = ForwardState {
+        id: String::new(),
+        pid: 0,
+        pid_start: None,
+        target: spec.target.clone(),
+        namespace: spec.namespace.clone(),
+        ports: spec.ports.clone(),
+        auto: spec.auto,
+        profile: profile.map(str::to_string),
+        started_at: chrono::Utc::now().to_rfc3339(),
+        pod: None,
+        mappings: Vec::new(),
+        reconnects: 0,
+    };
+    // Saved before spawning; the background process fills in its pid
+    state.save_new()?;
+
+    let log = File::create(state.log_path())?;
+    let mut cmd = Command::new(std::env::current_exe()?);
+    cmd.arg("forward").arg(&spec.target).args(&spec.ports);
+    if let Some(ns) = &spec.namespace {
+        cmd.args(["-n", ns]);
+    }
+    if spec.auto {
+        cmd.arg("--auto");
+    }
+    cmd.env(FORWARD_ID_ENV, &state.id)
+        .stdin(Stdio::null())
+        .stdout(Stdio::from(log.try_clone()?))
+        .stderr(Stdio::from(log));
+    // New session: survives the terminal closing, and gives `stop` a process
+    // group to signal that includes kubectl
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let mut child = cmd.spawn()?;
+
+    // Wait for the first connection so errors show up here, not in a log
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(10) {
+        if child.try_wait()?.is_some() {
+            let log = fs::read_to_string(state.log_path()).unwrap_or_default();
+            state.remove();
+            anyhow::bail!(
+                "Forward to {} failed to start:\n{}",
+                spec.target,
+                log.trim()
+            );
+        }
+
+        let current = ForwardState::load(&state.id)?;
+        if !current.mappings.is_empty() {
+            println!(
+                "{} Forward {} started: {} -> {} ({})",
+                "[INFO]".green(),
+                current.id.bold(),
+                current
+                    .mappings
+                    .iter()
+                    .map(|m| format!("localhost:{}", m.split(':').next().unwrap_or("?")))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                    .green(),
+                current.pod.as_deref().unwrap_or("?").cyan(),
+                spec.target.bright_black()
+            );
+            return Ok(());
+        }
+
+        thread::sleep(Duration::from_millis(200));
+    }
+
+    println!(
+        "{} Forward {} started, still connecting (log: {})",
+        "[INFO]".yellow(),
+        state.id.bold(),
+        state.log_path().display()
+    );
+    Ok(())
+}
+
+/// Show background forwards and whether their local ports answer
+pub fn list_forwards() -> Result<()> {
+    let forwards = ForwardState::load_all();
+    if forwards.is_empty() {
+        println!("{} No background forwards running", "[INFO]".yellow());
+        return Ok(());
+    }
+
+    println!("{}", "Forwards:".cyan().bold());
+    println!("{}", "-".repeat(100));
     println!(
-        "{} Port forwarding: localhost:{} -> {}:{} (namespace: {})",
-        "[INFO]".cyan(),
-        local_port,
-        pod_name.bold(),
-        pod_port,
-        ns.bright_black()
+        "{:<4} {:<26} {:<24} {:<24} {:<12} {:>6} {:>6}",
+        "ID", "TARGET", "PORTS", "POD", "STATUS", "RECONN", "AGE"
     );
-    println!("{} Press Ctrl+C to stop", "[INFO]".yellow());
     println!("{}", "-".repeat(100));
 
-    let status = Command::new("kubectl")
-        .args([
-            "port-forward",
-            &pod_name,
-            &format!("{}:{}", local_port, pod_port),
-            "-n",
-            &ns,
-        ])
-        .status()?;
+    for forward in &forwards {
+        let status = if !forward.is_running() {
+            "dead".red()
+        } else {
+            match forward.listening_ports() {
+                (_, 0) => "connecting".yellow(),
+                (up, total) if up == total => "healthy".green(),
+                _ => "degraded".yellow(),
+            }
+        };
+        let target = match &forward.profile {
+            Some(profile) => format!("{} ({})", forward.target, profile),
+            None => forward.target.clone(),
+        };
+        let ports = if forward.mappings.is_empty() {
+            forward.ports.join(",")
+        } else {
+            forward.mappings.join(",")
+        };
+
+        println!(
+            "{:<4} {:<26} {:<24} {:<24} {:<12} {:>6} {:>6}",
+            forward.id.bold(),
+            target,
+            ports,
+            forward.pod.as_deref().unwrap_or("-").cyan(),
+            status,
+            forward.reconnects,
+            calculate_age(&forward.started_at)
+        );
+    }
+
+    println!(
+        "\nLogs: {}",
+        get_forwards_dir().display().to_string().bright_black()
+    );
+
+    Ok(())
+}
+
+/// Stop a forward by id, every forward of a profile, or "all"
+pub fn stop_forwards(which: &str) -> Result<()> {
+    let forwards = ForwardState::load_all();
+    let selected: Vec<&ForwardState> = forwards
+        .iter()
+        .filter(|f| which == "all" || f.id == which || f.profile.as_deref() == Some(which))
+        .collect();
+
+    if selected.is_empty() {
+        anyhow::bail!("No forward or profile matching '{}'", which);
+    }
 
-    if !status.success() {
-        anyhow::bail!("Port forwarding failed");
+    for forward in selected {
+        forward.stop();
+        println!(
+            "{} Stopped forward {} ({})",
+            "[INFO]".green(),
+            forward.id.bold(),
+            forward.target
+        );
     }
 
     Ok(())
 }
+
+/// Bring up every forward of a profile from ~/.kdbg/config.json
+pub fn forward_up(profile: &str) -> Result<()> {
+    let config = Config::load()?;
+    let Some(specs) = config.forward.profiles.get(profile) else {
+        let names: Vec<&str> = config.forward.profiles.keys().map(String::as_str).collect();
+        anyhow::bail!(
+            "No forward profile '{}' (available: {})",
+            profile,
+            if names.is_empty() {
+                "none".to_string()
+            } else {
+                names.join(", ")
+            }
+        );
+    };
+
+    let running = ForwardState::load_all();
+    let mut failed = 0;
+    for spec in specs {
+        let already = running.iter().any(|f| {
+            f.is_running()
+                && f.profile.as_deref() == Some(profile)
+                && f.target == spec.target
+                && f.ports == spec.ports
+        });
+        if already {
+            println!("{} {} is already running", "[INFO]".yellow(), spec.target);
+            continue;
+        }
+
+        if let Err(e) = start_forward(spec, Some(profile)) {
+            eprintln!("{} {:#}", "[ERROR]".red(), e);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} forwards failed to start", failed, specs.len());
+    }
+
+    Ok(())
+}
+
+/// Run kubectl port-forward until it exits or the pod goes away. The
+/// connection is recorded (for `forward list` and `start_forward`) once
+/// kubectl reports every local port listening. Returns why the tunnel ended.
+fn run_tunnel(resolved: &Resolved, mappings: &[String], reconnect: bool) -> Result<String> {
+    let pod_ref = format!("pod/{}", resolved.pod);
+    let mut args = vec!["port-forward", &pod_ref, "-n", &resolved.namespace];
+    args.extend(mappings.iter().map(String::as_str));
+
+    let mut child = Command::new("kubectl")
+        .args(&args)
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    // Pass kubectl's output through, noting each local port it listens on
+    let stdout = child
+        .stdout
+        .take()
+        .context("Failed to read kubectl output")?;
+    let (tx, listening) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+            println!("{}", line);
+            if let Some(port) = forwarding_port(&line) {
+                let _ = tx.send(port);
+            }
+        }
+    });
+
+    let mut up: HashSet<u16> = HashSet::new();
+    let mut recorded = false;
+    let mut last_check = Instant::now();
+
+    loop {
+        up.extend(listening.try_iter());
+        if !recorded && resolved.ports.iter().all(|(local, _)| up.contains(local)) {
+            record_connection(&resolved.pod, mappings, reconnect);
+            recorded = true;
+        }
+
+        if let Some(status) = child.try_wait()? {
+            return Ok(match status.code() {
+                Some(code) => format!("Connection lost (kubectl exited with {})", code),
+                None => "Connection lost (kubectl was killed)".to_string(),
+            });
+        }
+
+        if last_check.elapsed() >= POD_CHECK_INTERVAL {
+            last_check = Instant::now();
+            if !pod_is_serving(&resolved.pod, &resolved.namespace) {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Ok(format!("Pod {} is gone or terminating", resolved.pod));
+            }
+        }
+
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+/// Local port of a "Forwarding from 127.0.0.1:8080 -> 80" line
+fn forwarding_port(line: &str) -> Option<u16> {
+    let address = line
+        .strip_prefix("Forwarding from ")?
+        .split(" -> ")
+        .next()?;
+    address.rsplit(':').next()?.parse().ok()
+}
+
+fn resolve(
+    target: &Target,
+    namespace: Option<String>,
+    specs: &[PortSpec],
+    locals: &mut [Option<u16>],
+    first: bool,
+) -> Result<Resolved> {
+    let (pod, service) = match target {
+        // Start strict like every other command; afterwards any healthy pod
+        // matching the pattern (e.g. the replacement replica) will do
+        Target::Pod(pattern) if first => {
+            let (name, ns) = find_pod(pattern, namespace)?;
+            (get_pod_json(&name, &ns)?, None)
+        }
+        Target::Pod(pattern) => (pick_pod(find_pods(pattern, namespace, None)?)?, None),
+        Target::Service(name) => {
+            let service = get_resource_json("service", name, namespace.as_deref())?;
+            let selector = selector_string(&service["spec"]["selector"])
+                .with_context(|| format!("Service '{}' has no selector", name))?;
+            let ns = service["metadata"]["namespace"]
+                .as_str()
+                .map(str::to_string);
+            (pods_for_selector(&selector, ns, name)?, Some(service))
+        }
+        Target::Deployment(name) => {
+            let deployment = get_resource_json("deployment", name, namespace.as_deref())?;
+            let selector = selector_string(&deployment["spec"]["selector"]["matchLabels"])
+                .with_context(|| format!("Deployment '{}' has no selector", name))?;
+            let ns = deployment["metadata"]["namespace"]
+                .as_str()
+                .map(str::to_string);
+            (pods_for_selector(&selector, ns, name)?, None)
+        }
+    };
+
+    let mut ports = Vec::new();
+    for (spec, chosen) in specs.iter().zip(locals.iter_mut()) {
+        let remote = match &service {
+            Some(service) => service_target_port(service, &pod, &spec.remote)?,
+            None => container_port(&pod, &spec.remote)?,
+        };
+        let local = match (*chosen, spec.local) {
+            (Some(port), _) => port,
+            (None, LocalPort::Fixed(port)) => port,
+            (None, LocalPort::SameAsRemote) => remote,
+            (None, LocalPort::Auto) => free_local_port()?,
+        };
+        *chosen = Some(local);
+        ports.push((local, remote));
+    }
+
+    Ok(Resolved {
+        pod: pod["metadata"]["name"]
+            .as_str()
+            .unwrap_or("unknown")
+            .to_string(),
+        namespace: pod["metadata"]["namespace"]
+            .as_str()
+            .unwrap_or("default")
+            .to_string(),
+        ports,
+    })
+}
+
+fn get_resource_json(kind: &str, name: &str, namespace: Option<&str>) -> Result<Value> {
+    let mut args = vec!["get", kind, name, "-o", "json"];
+    if let Some(ns) = namespace {
+        args.extend(["-n", ns]);
+    }
+
+    let output = Command::new("kubectl").args(&args).output()?;
+    if !output.status.success() {
+        anyhow::bail!("Failed to get {} '{}'", kind, name);
+    }
+
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+fn selector_string(labels: &Value) -> Option<String> {
+    let labels = labels.as_object().filter(|l| !l.is_empty())?;
+    Some(
+        labels
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v.as_str().unwrap_or("")))
+            .collect::<Vec<_>>()
+            .join(","),
+    )
+}
+
+fn pods_for_selector(selector: &str, namespace: Option<String>, owner: &str) -> Result<Value> {
+    let pods = find_pods("", namespace, Some(selector))
+        .map_err(|_| anyhow::anyhow!("No pods behind '{}' ({})", owner, selector))?;
+    pick_pod(pods)
+}
+
+/// Prefer running, ready, not-terminating pods, newest first
+fn pick_pod(pods: Vec<Value>) -> Result<Value> {
+    let mut candidates: Vec<Value> = pods
+        .into_iter()
+        .filter(|p| p["status"]["phase"].as_str() == Some("Running"))
+        .filter(|p| p["metadata"]["deletionTimestamp"].is_null())
+        .collect();
+
+    candidates.sort_by_key(|p| {
+        (
+            !is_ready(p),
+            std::cmp::Reverse(
+                p["metadata"]["creationTimestamp"]
+                    .as_str()
+                    .unwrap_or("")
+                    .to_string(),
+            ),
+        )
+    });
+
+    candidates
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No running pod available"))
+}
+
+fn is_ready(pod: &Value) -> bool {
+    pod["status"]["conditions"]
+        .as_array()
+        .and_then(|cs| cs.iter().find(|c| c["type"].as_str() == Some("Ready")))
+        .map(|c| c["status"].as_str() == Some("True"))
+        .unwrap_or(false)
+}
+
+fn pod_is_serving(pod: &str, namespace: &str) -> bool {
+    get_pod_json(pod, namespace)
+        .map(|p| {
+            p["status"]["phase"].as_str() == Some("Running")
+                && p["metadata"]["deletionTimestamp"].is_null()
+        })
+        .unwrap_or(false)
+}
+
+/// Resolve a numeric or named container port
+fn container_port(pod: &Value, port: &str) -> Result<u16> {
+    if let Ok(number) = port.parse() {
+        return Ok(number);
+    }
+
+    let empty_vec = vec![];
+    pod["spec"]["containers"]
+        .as_array()
+        .unwrap_or(&empty_vec)
+        .iter()
+        .flat_map(|c| c["ports"].as_array().unwrap_or(&empty_vec))
+        .find(|p| p["name"].as_str() == Some(port))
+        .and_then(|p| p["containerPort"].as_u64())
+        .map(|p| p as u16)
+        .with_context(|| {
+            format!(
+                "No container port named '{}' in pod {}",
+                port,
+                pod["metadata"]["name"].as_str().unwrap_or("unknown")
+            )
+        })
+}
+
+/// Map a service port (number or name) to the pod port it targets
+fn service_target_port(service: &Value, pod: &Value, port: &str) -> Result<u16> {
+    let empty_vec = vec![];
+    let ports = service["spec"]["ports"].as_array().unwrap_or(&empty_vec);
+
+    let Some(service_port) = ports.iter().find(|p| {
+        p["name"].as_str() == Some(port)
+            || p["port"].as_u64().map(|n| n.to_string()) == Some(port.to_string())
+    }) else {
+        let available: Vec<String> = ports
+            .iter()
+            .map(|p| match p["name"].as_str() {
+                Some(name) => format!("{} ({})", p["port"], name),
+                None => p["port"].to_string(),
+            })
+            .collect();
+        anyhow::bail!(
+            "Service has no port '{}' (available: {})",
+            port,
+            available.join(", ")
+        );
+    };
+
+    match &service_port["targetPort"] {
+        Value::Number(n) => Ok(n.as_u64().unwrap_or(0) as u16),
+        Value::String(name) => container_port(pod, name),
+        _ => Ok(service_port["port"].as_u64().unwrap_or(0) as u16),
+    }
+}
+
+fn free_local_port() -> Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    Ok(listener.local_addr()?.port())
+}
+
+fn log_event(level: ColoredString, message: &str) {
+    println!(
+        "{} {} {}",
+        level,
+        chrono::Local::now()
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+            .bright_black(),
+        message
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(ports: &[&str], auto: bool) -> Result<Vec<(String, String)>> {
+        let ports: Vec<String> = ports.iter().map(|p| p.to_string()).collect();
+        Ok(PortSpec::parse_all(&ports, auto)?
+            .into_iter()
+            .map(|spec| {
+                let local = match spec.local {
+                    LocalPort::Fixed(port) => port.to_string(),
+                    LocalPort::SameAsRemote => "same".to_string(),
+                    LocalPort::Auto => "auto".to_string(),
+                };
+                (local, spec.remote)
+            })
+            .collect())
+    }
+
+    #[test]
+    fn port_spec_table() {
+        // (arguments, --auto, "LOCAL>REMOTE" per mapping)
+        let cases = [
+            ("8080", false, "same>8080"),
+            ("8080:80", false, "8080>80"),
+            ("8080:http", false, "8080>http"),
+            (":80", false, "auto>80"),
+            ("80", true, "auto>80"),
+            ("8080:80", true, "8080>80"),
+            ("8080:80 9090", false, "8080>80 same>9090"),
+            ("8080 http", false, "same>8080 same>http"),
+            ("8080 80 443", false, "same>8080 same>80 same>443"),
+            ("8080 80", true, "auto>8080 auto>80"),
+        ];
+
+        for (ports, auto, expected) in cases {
+            let ports: Vec<&str> = ports.split(' ').collect();
+            let expected: Vec<(String, String)> = expected
+                .split(' ')
+                .map(|m| {
+                    let (local, remote) = m.split_once('>').unwrap();
+                    (local.to_string(), remote.to_string())
+                })
+                .collect();
+            assert_eq!(parse(&ports, auto).unwrap(), expected, "{:?}", ports);
+        }
+    }
+
+    #[test]
+    fn forwarding_port_from_kubectl_output() {
+        assert_eq!(
+            forwarding_port("Forwarding from 127.0.0.1:8080 -> 80"),
+            Some(8080)
+        );
+        assert_eq!(
+            forwarding_port("Forwarding from [::1]:9090 -> 9090"),
+            Some(9090)
+        );
+        assert_eq!(forwarding_port("Handling connection for 8080"), None);
+    }
+
+    #[test]
+    fn port_spec_errors() {
+        for ports in [&["8080", "80"][..], &["abc:80"], &["8080:"], &["70000:80"]] {
+            assert!(parse(ports, false).is_err(), "{:?} should fail", ports);
+        }
+
+        let err = parse(&["8080", "80"], false).unwrap_err().to_string();
+        assert!(err.contains("use 8080:80"), "{}", err);
+    }
+}
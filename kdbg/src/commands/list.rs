@@ -2,6 +2,7 @@ use crate::kubectl::get_pods_json;
 use crate::utils::calculate_age;
 use anyhow::Result;
 use colored::*;
+use serde_json::Value;
 
 pub fn list_pods(namespace: Option<String>, verbose: bool) -> Result<()> {
     let json = get_pods_json(namespace)?;
@@ -10,6 +11,13 @@ pub fn list_pods(namespace: Option<String>, verbose: bool) -> Result<()> {
     let pods = json["items"].as_array().unwrap_or(&empty_vec);
 
     println!("{}", "Pods:".cyan().bold());
+    print_pods(pods, verbose);
+
+    Ok(())
+}
+
+/// Render a pod table (shared by `list` and `nodes <node>`)
+pub fn print_pods(pods: &[Value], verbose: bool) {
     println!("{}", "-".repeat(100));
 
     if verbose {
@@ -67,6 +75,4 @@ pub fn list_pods(namespace: Option<String>, verbose: bool) -> Result<()> {
     }
 
     println!("\nTotal: {} pods", pods.len());
-
-    Ok(())
 }
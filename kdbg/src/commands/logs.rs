@@ -1,6 +1,10 @@
-use crate::kubectl::find_pod;
+use crate::commands::multilogs::summarize_logs;
+use crate::kubectl::{find_pod, get_pod_json};
 use anyhow::Result;
 use colored::*;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 pub fn show_logs(
@@ -8,9 +12,28 @@ pub fn show_logs(
     namespace: Option<String>,
     follow: bool,
     tail: u32,
+    save: Option<&Path>,
+    archive: bool,
+    summarize: bool,
 ) -> Result<()> {
     let (pod_name, ns) = find_pod(pod_pattern, namespace)?;
 
+    if let Some(dir) = save {
+        println!(
+            "{} Saving logs for pod: {} (namespace: {}) to {}",
+            "[INFO]".cyan(),
+            pod_name.bold(),
+            ns.bright_black(),
+            dir.display()
+        );
+        println!("{}", "-".repeat(100));
+
+        let pod = get_pod_json(&pod_name, &ns)?;
+        save_pod_logs(&pod, dir, tail)?;
+        finish_bundle(dir, archive)?;
+        return Ok(());
+    }
+
     println!(
         "{} Logs for pod: {} (namespace: {})",
         "[INFO]".cyan(),
@@ -19,6 +42,10 @@ pub fn show_logs(
     );
     println!("{}", "-".repeat(100));
 
+    if summarize {
+        return summarize_logs(&[(pod_name, ns)], follow, tail);
+    }
+
     let tail_str = tail.to_string();
     let mut args = vec!["logs", &pod_name, "-n", &ns, "--tail", &tail_str];
 
@@ -34,3 +61,170 @@ pub fn show_logs(
 
     Ok(())
 }
+
+/// Write one log file per container (plus the previous instance when the
+/// container has restarted) and a `<pod>.metadata.json` describing the capture
+pub fn save_pod_logs(pod: &Value, dir: &Path, tail: u32) -> Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let name = pod["metadata"]["name"].as_str().unwrap_or("unknown");
+    let ns = pod["metadata"]["namespace"].as_str().unwrap_or("default");
+    let tail_str = tail.to_string();
+
+    let mut windows = Vec::new();
+    let mut containers = Vec::new();
+
+    for container in pod_containers(pod) {
+        let cname = container["name"].as_str().unwrap_or("unknown");
+        let restarts = container_restarts(pod, cname);
+
+        let mut instances = vec![(format!("{}_{}.log", name, cname), false)];
+        if restarts > 0 {
+            instances.push((format!("{}_{}.previous.log", name, cname), true));
+        }
+
+        let mut files = Vec::new();
+        for (filename, previous) in instances {
+            let mut args = vec![
+                "logs",
+                name,
+                "-n",
+                ns,
+                "-c",
+                cname,
+                "--tail",
+                &tail_str,
+                "--timestamps",
+            ];
+            if previous {
+                args.push("--previous");
+            }
+
+            let output = Command::new("kubectl").args(&args).output()?;
+            if !output.status.success() {
+                eprintln!(
+                    "{} No {} logs for {}/{}",
+                    "[WARN]".yellow(),
+                    if previous { "previous" } else { "current" },
+                    name,
+                    cname
+                );
+                continue;
+            }
+
+            let logs = String::from_utf8_lossy(&output.stdout);
+            windows.extend(log_time_window(&logs));
+
+            fs::write(dir.join(&filename), output.stdout.as_slice())?;
+            println!(
+                "  {} {} ({} lines)",
+                "●".green(),
+                filename,
+                logs.lines().count()
+            );
+            files.push(filename);
+        }
+
+        containers.push(json!({
+            "name": cname,
+            "image": container["image"],
+            "restart_count": restarts,
+            "files": files,
+        }));
+    }
+
+    let metadata = json!({
+        "pod": name,
+        "namespace": ns,
+        "node": pod["spec"]["nodeName"],
+        "phase": pod["status"]["phase"],
+        "containers": containers,
+        "tail": tail,
+        "time_window": {
+            "from": windows.iter().map(|(first, _)| first).min(),
+            "to": windows.iter().map(|(_, last)| last).max(),
+        },
+        "captured_at": chrono::Utc::now().to_rfc3339(),
+    });
+
+    fs::write(
+        dir.join(format!("{}.metadata.json", name)),
+        serde_json::to_string_pretty(&metadata)?,
+    )?;
+
+    Ok(())
+}
+
+/// Print where the bundle went, packaging it as `<dir>.tar.gz` if requested
+pub fn finish_bundle(dir: &Path, archive: bool) -> Result<()> {
+    if archive {
+        let tarball = archive_dir(dir)?;
+        println!(
+            "{} Bundle written to {}",
+            "[SUCCESS]".green(),
+            tarball.display().to_string().bold()
+        );
+    } else {
+        println!(
+            "{} Logs written to {}",
+            "[SUCCESS]".green(),
+            dir.display().to_string().bold()
+        );
+    }
+
+    Ok(())
+}
+
+fn archive_dir(dir: &Path) -> Result<PathBuf> {
+    let dir = dir.canonicalize()?;
+    let parent = dir.parent().unwrap_or(Path::new("/"));
+    let base = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| anyhow::anyhow!("Invalid bundle directory"))?;
+    let tarball = parent.join(format!("{}.tar.gz", base));
+
+    let status = Command::new("tar")
+        .arg("czf")
+        .arg(&tarball)
+        .arg("-C")
+        .arg(parent)
+        .arg(base)
+        .status()?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to create {}", tarball.display());
+    }
+
+    Ok(tarball)
+}
+
+pub(crate) fn pod_containers(pod: &Value) -> Vec<&Value> {
+    ["initContainers", "containers"]
+        .iter()
+        .filter_map(|key| pod["spec"][key].as_array())
+        .flatten()
+        .collect()
+}
+
+pub(crate) fn container_restarts(pod: &Value, container: &str) -> u64 {
+    ["initContainerStatuses", "containerStatuses"]
+        .iter()
+        .filter_map(|key| pod["status"][key].as_array())
+        .flatten()
+        .find(|c| c["name"].as_str() == Some(container))
+        .and_then(|c| c["restartCount"].as_u64())
+        .unwrap_or(0)
+}
+
+/// First and last timestamps of `kubectl logs --timestamps` output
+fn log_time_window(logs: &str) -> Option<(String, String)> {
+    let stamp = |line: &str| line.split_whitespace().next().map(str::to_string);
+    let mut lines = logs.lines().filter(|l| !l.trim().is_empty());
+    let first = stamp(lines.next()?)?;
+    let last = lines
+        .next_back()
+        .and_then(stamp)
+        .unwrap_or_else(|| first.clone());
+    Some((first, last))
+}
@@ -1,31 +1,41 @@
 // Command implementations
+pub mod cp;
 pub mod ctx;
 pub mod debug;
 pub mod describe;
+pub mod diagnose;
 pub mod events;
 pub mod exec;
 pub mod forward;
 pub mod list;
 pub mod logs;
 pub mod multilogs;
+pub mod nodes;
 pub mod plugin;
 pub mod restart;
+pub mod sessions;
 pub mod shell;
+pub mod timeline;
 pub mod top;
 pub mod watch;
 
 // Re-export all command functions
-pub use ctx::switch_context;
-pub use debug::debug_pod;
+pub use cp::copy_files;
+pub use ctx::{context_shell, switch_context, switch_namespace};
+pub use debug::{auto_gc, debug_pod, gc_debug_pods, DebugOptions};
 pub use describe::describe_pod;
-pub use events::show_events;
-pub use exec::exec_pod;
-pub use forward::port_forward;
+pub use diagnose::diagnose_pod;
+pub use events::{show_events, EventFilter};
+pub use exec::{command_argv, exec_all, exec_pod, FanOut};
+pub use forward::{forward_up, list_forwards, port_forward, start_forward, stop_forwards};
 pub use list::list_pods;
 pub use logs::show_logs;
 pub use multilogs::multi_logs;
+pub use nodes::show_nodes;
 pub use plugin::run_plugin;
-pub use restart::restart_pod;
+pub use restart::{restart_all, restart_pod, RestartOptions};
+pub use sessions::{list_sessions, replay_session};
 pub use shell::shell_pod;
-pub use top::show_top;
+pub use timeline::show_timeline;
+pub use top::{show_top, show_top_nodes};
 pub use watch::watch_pods;
@@ -1,49 +1,36 @@
-use crate::kubectl::get_pods_json;
+use crate::commands::logs::{finish_bundle, save_pod_logs};
+use crate::kubectl::find_pods;
+use crate::summary::LogSummary;
+use crate::utils::pod_color;
 use anyhow::Result;
 use colored::*;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
 use std::process::{Command, Stdio};
 use std::sync::mpsc;
 use std::thread;
+use std::time::{Duration, Instant};
+
+/// How many templates the summary view shows
+const SUMMARY_TOP: usize = 20;
+
+/// How often the summary is redrawn in follow mode
+const SUMMARY_REFRESH: Duration = Duration::from_secs(2);
 
 pub fn multi_logs(
     pod_pattern: &str,
     namespace: Option<String>,
     follow: bool,
     tail: u32,
+    save: Option<&Path>,
+    archive: bool,
+    summarize: bool,
 ) -> Result<()> {
     // Find all matching pods
-    let json = get_pods_json(namespace.clone())?;
-    let empty_vec = vec![];
-    let pods = json["items"].as_array().unwrap_or(&empty_vec);
-
-    let matches: Vec<_> = pods
-        .iter()
-        .filter(|pod| {
-            let name = pod["metadata"]["name"].as_str().unwrap_or("");
-            name.contains(pod_pattern)
-        })
-        .collect();
-
-    if matches.is_empty() {
-        anyhow::bail!("No pods found matching '{}'", pod_pattern);
-    }
+    let matches = find_pods(pod_pattern, namespace, None)?;
 
     println!("{} Found {} matching pods:", "[INFO]".cyan(), matches.len());
 
-    let colors = [
-        Color::Green,
-        Color::Yellow,
-        Color::Blue,
-        Color::Magenta,
-        Color::Cyan,
-        Color::BrightGreen,
-        Color::BrightYellow,
-        Color::BrightBlue,
-        Color::BrightMagenta,
-        Color::BrightCyan,
-    ];
-
     let mut pod_list = Vec::new();
     for (i, pod) in matches.iter().enumerate() {
         let name = pod["metadata"]["name"]
@@ -54,7 +41,7 @@ pub fn multi_logs(
             .as_str()
             .unwrap_or("default")
             .to_string();
-        let color = colors[i % colors.len()];
+        let color = pod_color(i);
 
         println!("  {} {}", "●".color(color), name.color(color));
         pod_list.push((name, ns, color));
@@ -62,6 +49,22 @@ pub fn multi_logs(
 
     println!("{}", "-".repeat(100));
 
+    if let Some(dir) = save {
+        for pod in &matches {
+            save_pod_logs(pod, dir, tail)?;
+        }
+        finish_bundle(dir, archive)?;
+        return Ok(());
+    }
+
+    if summarize {
+        let pods: Vec<_> = pod_list
+            .into_iter()
+            .map(|(name, ns, _)| (name, ns))
+            .collect();
+        return summarize_logs(&pods, follow, tail);
+    }
+
     if !follow {
         // Non-follow mode: just get logs sequentially
         for (name, ns, color) in &pod_list {
@@ -96,10 +99,8 @@ pub fn multi_logs(
 
             if let Some(stdout) = child.stdout.take() {
                 let reader = BufReader::new(stdout);
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        let _ = tx.send((name.clone(), line, color));
-                    }
+                for line in reader.lines().map_while(Result::ok) {
+                    let _ = tx.send((name.clone(), line, color));
                 }
             }
 
@@ -117,3 +118,158 @@ pub fn multi_logs(
 
     Ok(())
 }
+
+/// Group log lines from the given pods into templates and print a summary.
+/// In follow mode the summary is kept up to date as new lines arrive.
+pub fn summarize_logs(pods: &[(String, String)], follow: bool, tail: u32) -> Result<()> {
+    let mut summary = LogSummary::new();
+    let tail_str = tail.to_string();
+
+    if !follow {
+        for (name, ns) in pods {
+            let output = Command::new("kubectl")
+                .args(["logs", name, "-n", ns, "--tail", &tail_str, "--timestamps"])
+                .output()?;
+
+            if output.status.success() {
+                for line in String::from_utf8_lossy(&output.stdout).lines() {
+                    summary.add(name, line);
+                }
+            }
+        }
+
+        print_summary(&summary, pods.len() > 1);
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel();
+
+    for (name, ns) in pods {
+        let (name, ns) = (name.clone(), ns.clone());
+        let tx = tx.clone();
+        let tail_str = tail_str.clone();
+        thread::spawn(move || {
+            let mut child = Command::new("kubectl")
+                .args([
+                    "logs",
+                    &name,
+                    "-n",
+                    &ns,
+                    "--tail",
+                    &tail_str,
+                    "--timestamps",
+                    "-f",
+                ])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+
+            if let Some(stdout) = child.stdout.take() {
+                let reader = BufReader::new(stdout);
+                for line in reader.lines().map_while(Result::ok) {
+                    let _ = tx.send((name.clone(), line));
+                }
+            }
+
+            let _ = child.wait();
+            Some(())
+        });
+    }
+
+    drop(tx);
+
+    let mut last_draw = Instant::now();
+    let mut dirty = true;
+    loop {
+        match rx.recv_timeout(SUMMARY_REFRESH) {
+            Ok((pod, line)) => {
+                summary.add(&pod, &line);
+                dirty = true;
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+
+        if dirty && last_draw.elapsed() >= SUMMARY_REFRESH {
+            print!("\x1B[2J\x1B[1;1H");
+            io::stdout().flush()?;
+            println!(
+                "{} {} {}",
+                "kdbg logs --summarize".cyan().bold(),
+                "-".bright_black(),
+                chrono::Local::now()
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+                    .bright_black()
+            );
+            print_summary(&summary, pods.len() > 1);
+            last_draw = Instant::now();
+            dirty = false;
+        }
+    }
+
+    print_summary(&summary, pods.len() > 1);
+    Ok(())
+}
+
+fn print_summary(summary: &LogSummary, per_pod: bool) {
+    println!(
+        "{} {} lines, {} distinct templates",
+        "[INFO]".cyan(),
+        summary.total(),
+        summary.distinct()
+    );
+    println!("{}", "-".repeat(100));
+    println!(
+        "{:<8} {:<7} {:<10} {:<10} {}",
+        "COUNT".bold(),
+        "%".bold(),
+        "FIRST".bold(),
+        "LAST".bold(),
+        "TEMPLATE".bold()
+    );
+    println!("{}", "-".repeat(100).bright_black());
+
+    let total = summary.total().max(1) as f64;
+    let clock = |t: Option<chrono::DateTime<chrono::Utc>>| {
+        t.map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| "-".to_string())
+    };
+
+    for (template, stats) in summary.top(SUMMARY_TOP) {
+        let mut shown: String = template.chars().take(60).collect();
+        if template.chars().count() > 60 {
+            shown.push_str("...");
+        }
+
+        println!(
+            "{:<8} {:<7} {:<10} {:<10} {}",
+            stats.count.to_string().yellow(),
+            format!("{:.1}", stats.count as f64 * 100.0 / total),
+            clock(stats.first_seen),
+            clock(stats.last_seen),
+            shown
+        );
+
+        if per_pod {
+            let pods: Vec<String> = stats
+                .pods
+                .iter()
+                .map(|(pod, count)| format!("{}={}", pod, count))
+                .collect();
+            println!("{:>37} {}", "", pods.join(" ").bright_black());
+        }
+    }
+
+    if summary.distinct() > SUMMARY_TOP {
+        println!(
+            "{}",
+            format!("... {} more templates", summary.distinct() - SUMMARY_TOP).bright_black()
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use crate::commands::list::print_pods;
+use crate::kubectl::{get_node_pods, get_nodes_json, get_pods_json};
+use crate::quantity::{format_cpu, format_memory, parse_cpu_millis, parse_memory_bytes};
+use crate::utils::calculate_age;
+use anyhow::Result;
+use colored::*;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Requested CPU (millicores), memory (bytes) and pod count on a node
+#[derive(Default)]
+struct Requested {
+    cpu: f64,
+    memory: f64,
+    pods: usize,
+}
+
+pub fn show_nodes(node_pattern: Option<&str>) -> Result<()> {
+    let json = get_nodes_json()?;
+    let empty_vec = vec![];
+    let nodes = json["items"].as_array().unwrap_or(&empty_vec);
+
+    if let Some(pattern) = node_pattern {
+        let node = find_node(nodes, pattern)?;
+        return show_node_pods(node);
+    }
+
+    let requested = requested_by_node(&get_pods_json(None)?);
+
+    println!("{}", "Nodes:".cyan().bold());
+    println!("{}", "-".repeat(100));
+    println!(
+        "{:<30} {:<20} {:<14} {:<10} {:>14} {:>14} {:>8}",
+        "NAME", "STATUS", "ROLES", "VERSION", "CPU REQ", "MEM REQ", "PODS"
+    );
+    println!("{}", "-".repeat(100));
+
+    for node in nodes {
+        let name = node["metadata"]["name"].as_str().unwrap_or("unknown");
+        let req = requested.get(name);
+        let allocatable = &node["status"]["allocatable"];
+
+        let cpu_alloc = allocatable["cpu"].as_str().and_then(parse_cpu_millis);
+        let mem_alloc = allocatable["memory"].as_str().and_then(parse_memory_bytes);
+        let max_pods = allocatable["pods"].as_str().unwrap_or("?");
+
+        println!(
+            "{:<30} {:<20} {:<14} {:<10} {:>14} {:>14} {:>8}",
+            name.cyan(),
+            node_status(node),
+            node_roles(node),
+            node["status"]["nodeInfo"]["kubeletVersion"]
+                .as_str()
+                .unwrap_or("-"),
+            utilization(req.map_or(0.0, |r| r.cpu), cpu_alloc, format_cpu),
+            utilization(req.map_or(0.0, |r| r.memory), mem_alloc, format_memory),
+            format!("{}/{}", req.map_or(0, |r| r.pods), max_pods)
+        );
+
+        let pressure = pressure_conditions(node);
+        if !pressure.is_empty() {
+            println!("  {} {}", "Pressure:".red(), pressure.join(", ").red());
+        }
+
+        let taints = node_taints(node);
+        if !taints.is_empty() {
+            println!(
+                "  {} {}",
+                "Taints:".bright_black(),
+                taints.join(", ").yellow()
+            );
+        }
+    }
+
+    println!("\nTotal: {} nodes", nodes.len());
+
+    Ok(())
+}
+
+fn show_node_pods(node: &Value) -> Result<()> {
+    let name = node["metadata"]["name"].as_str().unwrap_or("unknown");
+    let pods = get_node_pods(name)?;
+
+    println!(
+        "{} Node: {} ({}, {}, up {})",
+        "[INFO]".cyan(),
+        name.bold(),
+        node_status(node),
+        node_roles(node),
+        node["metadata"]["creationTimestamp"]
+            .as_str()
+            .map(calculate_age)
+            .unwrap_or_else(|| "unknown".to_string())
+    );
+    for line in [pressure_conditions(node), node_taints(node)] {
+        if !line.is_empty() {
+            println!("  {}", line.join(", ").yellow());
+        }
+    }
+    println!();
+
+    println!("{}", "Pods:".cyan().bold());
+    print_pods(&pods, true);
+
+    Ok(())
+}
+
+/// Find a node by pattern, exact name first, then fuzzy
+pub(crate) fn find_node<'a>(nodes: &'a [Value], pattern: &str) -> Result<&'a Value> {
+    let name = |n: &Value| n["metadata"]["name"].as_str().unwrap_or("").to_string();
+
+    if let Some(node) = nodes.iter().find(|n| name(n) == pattern) {
+        return Ok(node);
+    }
+
+    let matches: Vec<&Value> = nodes.iter().filter(|n| name(n).contains(pattern)).collect();
+    match matches.len() {
+        1 => Ok(matches[0]),
+        0 => anyhow::bail!("No nodes found matching '{}'", pattern),
+        _ => {
+            println!("{} Multiple nodes found:", "[INFO]".yellow());
+            for node in &matches {
+                println!("  - {}", name(node).cyan());
+            }
+            anyhow::bail!("Please be more specific")
+        }
+    }
+}
+
+/// Sum container requests of all non-terminated pods, per node
+fn requested_by_node(pods: &Value) -> HashMap<String, Requested> {
+    let mut requested: HashMap<String, Requested> = HashMap::new();
+    let empty_vec = vec![];
+
+    for pod in pods["items"].as_array().unwrap_or(&empty_vec) {
+        let Some(node) = pod["spec"]["nodeName"].as_str() else {
+            continue;
+        };
+        if matches!(
+            pod["status"]["phase"].as_str(),
+            Some("Succeeded") | Some("Failed")
+        ) {
+            continue;
+        }
+
+        let entry = requested.entry(node.to_string()).or_default();
+        entry.pods += 1;
+        for container in pod["spec"]["containers"].as_array().unwrap_or(&empty_vec) {
+            let requests = &container["resources"]["requests"];
+            entry.cpu += requests["cpu"]
+                .as_str()
+                .and_then(parse_cpu_millis)
+                .unwrap_or(0.0);
+            entry.memory += requests["memory"]
+                .as_str()
+                .and_then(parse_memory_bytes)
+                .unwrap_or(0.0);
+        }
+    }
+
+    requested
+}
+
+/// "Ready", "NotReady", optionally with ",SchedulingDisabled"
+pub(crate) fn node_status(node: &Value) -> ColoredString {
+    let ready = node["status"]["conditions"]
+        .as_array()
+        .and_then(|cs| cs.iter().find(|c| c["type"].as_str() == Some("Ready")))
+        .map(|c| c["status"].as_str() == Some("True"))
+        .unwrap_or(false);
+    let cordoned = node["spec"]["unschedulable"].as_bool().unwrap_or(false);
+
+    match (ready, cordoned) {
+        (true, false) => "Ready".green(),
+        (true, true) => "Ready,SchedulingDisabled".yellow(),
+        (false, false) => "NotReady".red(),
+        (false, true) => "NotReady,SchedulingDisabled".red(),
+    }
+}
+
+fn node_roles(node: &Value) -> String {
+    let roles: Vec<&str> = node["metadata"]["labels"]
+        .as_object()
+        .map(|labels| {
+            labels
+                .keys()
+                .filter_map(|k| k.strip_prefix("node-role.kubernetes.io/"))
+                .filter(|r| !r.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if roles.is_empty() {
+        "<none>".to_string()
+    } else {
+        roles.join(",")
+    }
+}
+
+/// Conditions other than Ready that are currently True
+fn pressure_conditions(node: &Value) -> Vec<String> {
+    node["status"]["conditions"]
+        .as_array()
+        .map(|cs| {
+            cs.iter()
+                .filter(|c| c["type"].as_str() != Some("Ready"))
+                .filter(|c| c["status"].as_str() == Some("True"))
+                .filter_map(|c| c["type"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn node_taints(node: &Value) -> Vec<String> {
+    node["spec"]["taints"]
+        .as_array()
+        .map(|taints| {
+            taints
+                .iter()
+                .map(|t| {
+                    let key = t["key"].as_str().unwrap_or("?");
+                    let effect = t["effect"].as_str().unwrap_or("?");
+                    match t["value"].as_str() {
+                        Some(value) => format!("{}={}:{}", key, value, effect),
+                        None => format!("{}:{}", key, effect),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// "1200m (60%)" style requested-vs-allocatable cell
+fn utilization(
+    requested: f64,
+    allocatable: Option<f64>,
+    format: fn(f64) -> String,
+) -> ColoredString {
+    let amount = format(requested);
+
+    match allocatable.filter(|a| *a > 0.0) {
+        Some(total) => {
+            let pct = requested * 100.0 / total;
+            let cell = format!("{} ({:.0}%)", amount, pct);
+            if pct >= 90.0 {
+                cell.red()
+            } else if pct >= 75.0 {
+                cell.yellow()
+            } else {
+                cell.normal()
+            }
+        }
+        None => amount.normal(),
+    }
+}
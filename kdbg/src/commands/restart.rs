@@ -1,10 +1,49 @@
-use crate::kubectl::find_pod;
-use anyhow::Result;
+use crate::kubectl::{find_pod, find_pods, get_pod_json, get_pods_json};
+use crate::utils::{confirm, format_duration};
+use anyhow::{Context, Result};
 use colored::*;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
 use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
 
-pub fn restart_pod(pod_pattern: &str, namespace: Option<String>) -> Result<()> {
+/// How often to check on the replacement pod
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct RestartOptions {
+    /// `kubectl rollout restart` the owning workload instead of deleting the pod
+    pub rollout: bool,
+    pub dry_run: bool,
+    /// Don't ask before deleting a pod nothing will recreate
+    pub yes: bool,
+    /// Seconds to wait for the replacement to become Ready
+    pub timeout: u64,
+}
+
+/// The controller a pod belongs to. `uid` is the direct owner (e.g. the
+/// ReplicaSet), `kind`/`name` the workload above it (e.g. the Deployment).
+struct Owner {
+    kind: String,
+    name: String,
+    uid: String,
+}
+
+impl Owner {
+    fn display(&self) -> String {
+        format!("{}/{}", self.kind, self.name)
+    }
+}
+
+pub fn restart_pod(
+    pod_pattern: &str,
+    namespace: Option<String>,
+    opts: &RestartOptions,
+) -> Result<()> {
     let (pod_name, ns) = find_pod(pod_pattern, namespace)?;
+    let pod = get_pod_json(&pod_name, &ns)?;
+    let owner = controller_of(&pod, &ns);
 
     println!(
         "{} Restarting pod: {} (namespace: {})",
@@ -12,24 +51,675 @@ pub fn restart_pod(pod_pattern: &str, namespace: Option<String>) -> Result<()> {
         pod_name.bold(),
         ns.bright_black()
     );
+    match &owner {
+        Some(owner) => println!("{} Managed by {}", "[INFO]".cyan(), owner.display().cyan()),
+        None => println!(
+            "{} Pod has no controller: once deleted it will NOT be recreated",
+            "[WARN]".yellow()
+        ),
+    }
+    println!("{}", "-".repeat(100));
+
+    if opts.rollout {
+        let owner = owner
+            .as_ref()
+            .filter(|o| matches!(o.kind.as_str(), "Deployment" | "StatefulSet" | "DaemonSet"))
+            .context("--rollout needs a pod owned by a Deployment, StatefulSet or DaemonSet")?;
+        return rollout_restart(owner, &ns, opts);
+    }
+
+    let Some(owner) = owner else {
+        if opts.dry_run {
+            println!(
+                "{} Would delete pod {} (not recreated)",
+                "[DRY RUN]".yellow(),
+                pod_name.bold()
+            );
+            return Ok(());
+        }
+        if !opts.yes && !confirm(&format!("Delete {} for good?", pod_name)) {
+            anyhow::bail!("Aborted, pod left running");
+        }
+        delete_pod(&pod_name, &ns)?;
+        println!("{} Pod deleted", "[SUCCESS]".green());
+        return Ok(());
+    };
+
+    if opts.dry_run {
+        println!(
+            "{} Would delete pod {} and wait up to {} for {} to replace it",
+            "[DRY RUN]".yellow(),
+            pod_name.bold(),
+            format_duration(opts.timeout as i64),
+            owner.display()
+        );
+        return Ok(());
+    }
+
+    let existing = owned_pod_uids(&ns, &owner.uid)?;
+    delete_pod(&pod_name, &ns)?;
+    println!("{} Pod deleted", "[INFO]".cyan());
+
+    let (replacement, _) = wait_for_replacement(&ns, &owner, &existing, opts.timeout)?;
     println!(
-        "{} This will delete the pod and let the controller recreate it",
-        "[INFO]".yellow()
+        "{} Replacement pod {} is Ready",
+        "[SUCCESS]".green(),
+        replacement.bold()
     );
+
+    Ok(())
+}
+
+/// Result of restarting one pod with `--all`
+enum Outcome {
+    Restarted(String),
+    Deleted,
+    Skipped(&'static str),
+    Failed(String),
+}
+
+struct BulkTarget {
+    name: String,
+    namespace: String,
+    labels: Value,
+    owner: Option<Owner>,
+}
+
+/// Restart every pod matching the pattern, `parallel` at a time. Each batch
+/// waits for PodDisruptionBudgets to allow it and for all replacements to be
+/// Ready before the next one starts; the first failure stops the run.
+/// Returns the process exit code.
+pub fn restart_all(
+    pod_pattern: &str,
+    namespace: Option<String>,
+    selector: Option<&str>,
+    parallel: usize,
+    opts: &RestartOptions,
+) -> Result<i32> {
+    let pods = find_pods(pod_pattern, namespace, selector)?;
+    let targets: Vec<BulkTarget> = pods
+        .iter()
+        .map(|pod| {
+            let ns = pod["metadata"]["namespace"].as_str().unwrap_or("default");
+            BulkTarget {
+                name: pod["metadata"]["name"]
+                    .as_str()
+                    .unwrap_or("unknown")
+                    .to_string(),
+                namespace: ns.to_string(),
+                labels: pod["metadata"]["labels"].clone(),
+                owner: controller_of(pod, ns),
+            }
+        })
+        .collect();
+    let parallel = parallel.max(1);
+
+    println!(
+        "{} Restarting {} pods, {} at a time:",
+        "[INFO]".cyan(),
+        targets.len(),
+        parallel
+    );
+    for target in &targets {
+        let owner = match &target.owner {
+            Some(owner) => owner.display().normal(),
+            None if opts.yes => "no controller, will not be recreated".red(),
+            None => "no controller, skipped without --yes".yellow(),
+        };
+        println!(
+            "  - {} {} ({})",
+            target.name.cyan(),
+            target.namespace.bright_black(),
+            owner
+        );
+    }
+    println!("{}", "-".repeat(100));
+
+    let mut outcomes: Vec<Option<(Outcome, Duration)>> = (0..targets.len()).map(|_| None).collect();
+    let mut todo = Vec::new();
+    for (i, target) in targets.iter().enumerate() {
+        if target.owner.is_some() || opts.yes {
+            todo.push(i);
+        } else {
+            outcomes[i] = Some((Outcome::Skipped("no controller"), Duration::ZERO));
+        }
+    }
+
+    if opts.dry_run {
+        let planned: Vec<&BulkTarget> = todo.iter().map(|&i| &targets[i]).collect();
+        for (ns, pdb) in disruption_budgets(&planned)? {
+            println!(
+                "{} PodDisruptionBudget {}/{} currently allows {} disruptions",
+                "[DRY RUN]".yellow(),
+                ns,
+                pdb["metadata"]["name"].as_str().unwrap_or("unknown"),
+                pdb["status"]["disruptionsAllowed"].as_i64().unwrap_or(0)
+            );
+        }
+        println!(
+            "{} Would restart {} pods in batches of {}",
+            "[DRY RUN]".yellow(),
+            todo.len(),
+            parallel
+        );
+        return Ok(0);
+    }
+
+    if todo.is_empty() {
+        println!("{} Nothing to restart", "[INFO]".yellow());
+        return Ok(0);
+    }
+    if !opts.yes && !confirm(&format!("Restart {} pods?", todo.len())) {
+        anyhow::bail!("Aborted, no pods restarted");
+    }
+
+    for batch in todo.chunks(parallel) {
+        let started = Instant::now();
+        let failed = run_batch(&targets, batch, opts.timeout, &mut outcomes)?;
+        // Also for a failed batch, so pods it did restart show their time
+        for &i in batch {
+            if let Some((_, elapsed)) = &mut outcomes[i] {
+                *elapsed = started.elapsed();
+            }
+        }
+        if let Some(failed) = failed {
+            println!(
+                "{} {}: {}",
+                "[ERROR]".red(),
+                targets[failed].name,
+                match &outcomes[failed] {
+                    Some((Outcome::Failed(err), _)) => err.as_str(),
+                    _ => "failed",
+                }
+            );
+            println!("{} Stopping at the first failure", "[WARN]".yellow());
+            break;
+        }
+    }
+
+    Ok(print_report(&targets, &outcomes))
+}
+
+/// Restart one batch. Returns the index of the first pod that failed, if any.
+fn run_batch(
+    targets: &[BulkTarget],
+    batch: &[usize],
+    timeout: u64,
+    outcomes: &mut [Option<(Outcome, Duration)>],
+) -> Result<Option<usize>> {
+    let batch_targets: Vec<&BulkTarget> = batch.iter().map(|&i| &targets[i]).collect();
+    if let Err(err) = wait_for_budgets(&batch_targets, timeout) {
+        for &i in batch {
+            outcomes[i] = Some((Outcome::Failed(err.to_string()), Duration::ZERO));
+        }
+        return Ok(batch.first().copied());
+    }
+
+    // Pods each controller already has, so replacements can be told apart
+    let mut existing: HashMap<String, HashSet<String>> = HashMap::new();
+    for target in &batch_targets {
+        if let Some(owner) = &target.owner {
+            if !existing.contains_key(&owner.uid) {
+                existing.insert(
+                    owner.uid.clone(),
+                    owned_pod_uids(&target.namespace, &owner.uid)?,
+                );
+            }
+        }
+    }
+
+    for &i in batch {
+        let target = &targets[i];
+        println!("{} Deleting {}", "[INFO]".cyan(), target.name.bold());
+        if let Err(err) = delete_pod(&target.name, &target.namespace) {
+            outcomes[i] = Some((Outcome::Failed(err.to_string()), Duration::ZERO));
+            return Ok(Some(i));
+        }
+        if target.owner.is_none() {
+            outcomes[i] = Some((Outcome::Deleted, Duration::ZERO));
+        }
+    }
+
+    for &i in batch {
+        let target = &targets[i];
+        let Some(owner) = &target.owner else {
+            continue;
+        };
+        let known = existing.entry(owner.uid.clone()).or_default();
+        match wait_for_replacement(&target.namespace, owner, known, timeout) {
+            Ok((name, uid)) => {
+                println!(
+                    "{} {} replaced by {}",
+                    "[SUCCESS]".green(),
+                    target.name,
+                    name.bold()
+                );
+                // Two pods of one controller in a batch need two replacements
+                known.insert(uid);
+                outcomes[i] = Some((Outcome::Restarted(name), Duration::ZERO));
+            }
+            Err(err) => {
+                outcomes[i] = Some((Outcome::Failed(err.to_string()), Duration::ZERO));
+                return Ok(Some(i));
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// PodDisruptionBudgets covering any of the targets, with their namespace
+fn disruption_budgets(targets: &[&BulkTarget]) -> Result<Vec<(String, Value)>> {
+    let mut namespaces: Vec<&str> = targets.iter().map(|t| t.namespace.as_str()).collect();
+    namespaces.sort();
+    namespaces.dedup();
+
+    let mut budgets = Vec::new();
+    for ns in namespaces {
+        let output = Command::new("kubectl")
+            .args(["get", "pdb", "-n", ns, "-o", "json"])
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to get PodDisruptionBudgets in {}", ns);
+        }
+        let json: Value = serde_json::from_slice(&output.stdout)?;
+        let empty_vec = vec![];
+        for pdb in json["items"].as_array().unwrap_or(&empty_vec) {
+            let covers_any = targets.iter().any(|t| {
+                t.namespace == ns && selector_matches(&pdb["spec"]["selector"], &t.labels)
+            });
+            if covers_any {
+                budgets.push((ns.to_string(), pdb.clone()));
+            }
+        }
+    }
+
+    Ok(budgets)
+}
+
+/// Wait until every PodDisruptionBudget covering the batch allows as many
+/// disruptions as the batch would cause
+fn wait_for_budgets(batch: &[&BulkTarget], timeout: u64) -> Result<()> {
+    let started = Instant::now();
+    let mut waited = false;
+
+    loop {
+        let blocked: Vec<String> = disruption_budgets(batch)?
+            .iter()
+            .filter_map(|(ns, pdb)| {
+                let needed = batch
+                    .iter()
+                    .filter(|t| {
+                        &t.namespace == ns && selector_matches(&pdb["spec"]["selector"], &t.labels)
+                    })
+                    .count() as i64;
+                let allowed = pdb["status"]["disruptionsAllowed"].as_i64().unwrap_or(0);
+                (allowed < needed).then(|| {
+                    format!(
+                        "{} (allows {}, batch needs {})",
+                        pdb["metadata"]["name"].as_str().unwrap_or("unknown"),
+                        allowed,
+                        needed
+                    )
+                })
+            })
+            .collect();
+
+        if blocked.is_empty() {
+            if waited {
+                println!();
+            }
+            return Ok(());
+        }
+
+        let elapsed = started.elapsed().as_secs();
+        if elapsed >= timeout {
+            if waited {
+                println!();
+            }
+            anyhow::bail!(
+                "PodDisruptionBudget did not allow the restart within {}: {}",
+                format_duration(timeout as i64),
+                blocked.join(", ")
+            );
+        }
+
+        waited = true;
+        show_progress(
+            elapsed,
+            &format!("waiting for PodDisruptionBudget {}", blocked.join(", "))
+                .yellow()
+                .to_string(),
+        )?;
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Whether a LabelSelector (matchLabels / matchExpressions) selects the labels.
+/// An empty selector selects everything; a missing one selects nothing, as
+/// for a policy/v1 PodDisruptionBudget without a selector.
+fn selector_matches(selector: &Value, labels: &Value) -> bool {
+    if selector.is_null() {
+        return false;
+    }
+    let label = |key: &str| labels[key].as_str();
+
+    let labels_match = selector["matchLabels"]
+        .as_object()
+        .map(|wanted| {
+            wanted
+                .iter()
+                .all(|(key, value)| label(key) == value.as_str())
+        })
+        .unwrap_or(true);
+
+    let expressions_match = selector["matchExpressions"]
+        .as_array()
+        .map(|expressions| {
+            expressions.iter().all(|expr| {
+                let key = expr["key"].as_str().unwrap_or("");
+                let in_values = |v: &str| {
+                    expr["values"]
+                        .as_array()
+                        .is_some_and(|vs| vs.iter().any(|x| x.as_str() == Some(v)))
+                };
+                match expr["operator"].as_str() {
+                    Some("In") => label(key).is_some_and(in_values),
+                    Some("NotIn") => !label(key).is_some_and(in_values),
+                    Some("Exists") => label(key).is_some(),
+                    Some("DoesNotExist") => label(key).is_none(),
+                    _ => false,
+                }
+            })
+        })
+        .unwrap_or(true);
+
+    labels_match && expressions_match
+}
+
+/// Print the per-pod summary of a bulk restart; returns the exit code
+fn print_report(targets: &[BulkTarget], outcomes: &[Option<(Outcome, Duration)>]) -> i32 {
     println!("{}", "-".repeat(100));
+    println!(
+        "{:<45} {:<15} {:<12} {:<20} {}",
+        "POD".bold(),
+        "NAMESPACE".bold(),
+        "RESULT".bold(),
+        "REPLACEMENT".bold(),
+        "TIME".bold()
+    );
+    println!("{}", "-".repeat(100).bright_black());
+
+    let mut restarted = 0;
+    let mut failed = 0;
+    for (target, outcome) in targets.iter().zip(outcomes) {
+        let (result, replacement) = match outcome {
+            Some((Outcome::Restarted(pod), _)) => {
+                restarted += 1;
+                ("restarted".green(), pod.as_str())
+            }
+            Some((Outcome::Deleted, _)) => {
+                restarted += 1;
+                ("deleted".yellow(), "-")
+            }
+            Some((Outcome::Skipped(reason), _)) => ("skipped".bright_black(), *reason),
+            Some((Outcome::Failed(_), _)) => {
+                failed += 1;
+                ("failed".red(), "-")
+            }
+            None => ("not run".bright_black(), "-"),
+        };
+        let time = match outcome {
+            Some((Outcome::Restarted(_) | Outcome::Deleted, elapsed)) => {
+                format!("{:.1}s", elapsed.as_secs_f64())
+            }
+            _ => "-".to_string(),
+        };
+
+        println!(
+            "{:<45} {:<15} {:<12} {:<20} {}",
+            target.name.cyan(),
+            target.namespace.bright_black(),
+            result,
+            replacement,
+            time
+        );
+    }
+
+    println!(
+        "\nTotal: {} pods, {} restarted, {} failed",
+        targets.len(),
+        restarted.to_string().green(),
+        failed.to_string().red()
+    );
+
+    if failed > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Find the pod's controller, following ReplicaSets up to their Deployment
+fn controller_of(pod: &Value, namespace: &str) -> Option<Owner> {
+    let reference = controller_reference(pod)?;
+    let mut owner = Owner {
+        kind: reference["kind"].as_str()?.to_string(),
+        name: reference["name"].as_str()?.to_string(),
+        uid: reference["uid"].as_str()?.to_string(),
+    };
+
+    if owner.kind == "ReplicaSet" {
+        let output = Command::new("kubectl")
+            .args([
+                "get",
+                "replicaset",
+                &owner.name,
+                "-n",
+                namespace,
+                "-o",
+                "json",
+            ])
+            .output()
+            .ok()?;
+        if output.status.success() {
+            let rs: Value = serde_json::from_slice(&output.stdout).ok()?;
+            if let Some(deployment) = controller_reference(&rs) {
+                if deployment["kind"].as_str() == Some("Deployment") {
+                    owner.kind = "Deployment".to_string();
+                    owner.name = deployment["name"].as_str()?.to_string();
+                }
+            }
+        }
+    }
+
+    Some(owner)
+}
+
+fn controller_reference(object: &Value) -> Option<&Value> {
+    object["metadata"]["ownerReferences"]
+        .as_array()?
+        .iter()
+        .find(|r| r["controller"].as_bool().unwrap_or(false))
+}
+
+/// UIDs of the pods currently owned by `owner_uid`
+fn owned_pod_uids(namespace: &str, owner_uid: &str) -> Result<HashSet<String>> {
+    let json = get_pods_json(Some(namespace.to_string()))?;
+    let empty_vec = vec![];
+
+    Ok(json["items"]
+        .as_array()
+        .unwrap_or(&empty_vec)
+        .iter()
+        .filter(|pod| is_owned_by(pod, owner_uid))
+        .filter_map(|pod| pod["metadata"]["uid"].as_str().map(str::to_string))
+        .collect())
+}
+
+fn is_owned_by(pod: &Value, owner_uid: &str) -> bool {
+    controller_reference(pod).and_then(|r| r["uid"].as_str()) == Some(owner_uid)
+}
+
+/// Poll until a pod of `owner` that wasn't in `existing` is Ready, showing
+/// its progress on a single line. Returns the replacement's name and uid.
+fn wait_for_replacement(
+    namespace: &str,
+    owner: &Owner,
+    existing: &HashSet<String>,
+    timeout: u64,
+) -> Result<(String, String)> {
+    let started = Instant::now();
+    let mut last_seen: Option<String> = None;
+
+    loop {
+        let elapsed = started.elapsed().as_secs();
+        if elapsed >= timeout {
+            println!();
+            match &last_seen {
+                Some(pod) => println!(
+                    "{} Check why it isn't Ready: kdbg diagnose {} -n {}",
+                    "[TIP]".cyan(),
+                    pod,
+                    namespace
+                ),
+                None => println!(
+                    "{} Check the controller: kubectl describe {} -n {}",
+                    "[TIP]".cyan(),
+                    owner.display().to_lowercase(),
+                    namespace
+                ),
+            }
+            anyhow::bail!(
+                "No Ready replacement from {} after {}",
+                owner.display(),
+                format_duration(timeout as i64)
+            );
+        }
+
+        let json = get_pods_json(Some(namespace.to_string()))?;
+        let empty_vec = vec![];
+        let replacement = json["items"]
+            .as_array()
+            .unwrap_or(&empty_vec)
+            .iter()
+            .filter(|pod| is_owned_by(pod, &owner.uid))
+            .filter(|pod| pod["metadata"]["deletionTimestamp"].is_null())
+            .find(|pod| {
+                pod["metadata"]["uid"]
+                    .as_str()
+                    .is_some_and(|uid| !existing.contains(uid))
+            });
+
+        let progress = match replacement {
+            Some(pod) => {
+                let name = pod["metadata"]["name"].as_str().unwrap_or("unknown");
+                if is_ready(pod) {
+                    println!();
+                    let uid = pod["metadata"]["uid"].as_str().unwrap_or_default();
+                    return Ok((name.to_string(), uid.to_string()));
+                }
+                last_seen = Some(name.to_string());
+                format!("{} {}", name.cyan(), pod_progress(pod).yellow())
+            }
+            None => "waiting for the controller to create a pod"
+                .bright_black()
+                .to_string(),
+        };
 
+        show_progress(elapsed, &progress)?;
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Overwrite the current terminal line with a progress message
+fn show_progress(elapsed: u64, message: &str) -> Result<()> {
+    print!(
+        "\r\x1B[K{} [{:>4}] {}",
+        "[INFO]".cyan(),
+        format_duration(elapsed as i64),
+        message
+    );
+    io::stdout().flush()?;
+    Ok(())
+}
+
+fn is_ready(pod: &Value) -> bool {
+    pod["status"]["conditions"]
+        .as_array()
+        .and_then(|cs| cs.iter().find(|c| c["type"].as_str() == Some("Ready")))
+        .is_some_and(|c| c["status"].as_str() == Some("True"))
+}
+
+/// Short status of a pod that isn't Ready yet, e.g. "Pending (ContainerCreating)"
+fn pod_progress(pod: &Value) -> String {
+    let phase = pod["status"]["phase"].as_str().unwrap_or("Pending");
+    let reason = pod["status"]["containerStatuses"]
+        .as_array()
+        .and_then(|cs| {
+            cs.iter()
+                .find_map(|c| c["state"]["waiting"]["reason"].as_str())
+        });
+
+    match reason {
+        Some(reason) => format!("{} ({})", phase, reason),
+        None if phase == "Running" => "Running (not Ready)".to_string(),
+        None => phase.to_string(),
+    }
+}
+
+fn delete_pod(pod_name: &str, namespace: &str) -> Result<()> {
     let status = Command::new("kubectl")
-        .args(["delete", "pod", &pod_name, "-n", &ns])
+        .args(["delete", "pod", pod_name, "-n", namespace, "--wait=false"])
         .status()?;
 
     if !status.success() {
         anyhow::bail!("Failed to delete pod");
     }
 
-    println!(
-        "{} Pod deleted. Waiting for recreation...",
-        "[SUCCESS]".green()
-    );
+    Ok(())
+}
+
+fn rollout_restart(owner: &Owner, namespace: &str, opts: &RestartOptions) -> Result<()> {
+    let resource = format!("{}/{}", owner.kind.to_lowercase(), owner.name);
+
+    if opts.dry_run {
+        println!(
+            "{} Would run: kubectl rollout restart {} -n {}",
+            "[DRY RUN]".yellow(),
+            resource,
+            namespace
+        );
+        return Ok(());
+    }
+
+    let status = Command::new("kubectl")
+        .args(["rollout", "restart", &resource, "-n", namespace])
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("Failed to restart {}", owner.display());
+    }
+
+    // rollout status prints per-replica progress and exits once done
+    let status = Command::new("kubectl")
+        .args([
+            "rollout",
+            "status",
+            &resource,
+            "-n",
+            namespace,
+            &format!("--timeout={}s", opts.timeout),
+        ])
+        .status()?;
+    if !status.success() {
+        anyhow::bail!(
+            "{} did not finish rolling out within {}",
+            owner.display(),
+            format_duration(opts.timeout as i64)
+        );
+    }
+
+    println!("{} {} restarted", "[SUCCESS]".green(), owner.display());
 
     Ok(())
 }
@@ -0,0 +1,166 @@
+use crate::recorder::get_sessions_dir;
+use anyhow::Result;
+use colored::*;
+use serde_json::Value;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+/// List recorded sessions, newest first
+pub fn list_sessions() -> Result<()> {
+    let recordings = find_recordings()?;
+
+    println!("{}", "Recorded sessions:".cyan().bold());
+    println!("{}", "-".repeat(100));
+
+    if recordings.is_empty() {
+        println!("  (none - record one with 'kdbg shell <pod> --record')");
+        return Ok(());
+    }
+
+    println!(
+        "{:<36} {:<20} {:<15} {:<10} {}",
+        "ID".bold(),
+        "CONTEXT".bold(),
+        "NAMESPACE".bold(),
+        "USER".bold(),
+        "DURATION".bold()
+    );
+    println!("{}", "-".repeat(100).bright_black());
+
+    for path in &recordings {
+        let id = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("unknown");
+        let file = fs::File::open(path)?;
+        let mut lines = BufReader::new(file).lines().map_while(Result::ok);
+
+        let header: Value = lines
+            .next()
+            .and_then(|l| serde_json::from_str(&l).ok())
+            .unwrap_or_default();
+        let duration = lines
+            .last()
+            .and_then(|l| serde_json::from_str::<Value>(&l).ok())
+            .and_then(|event| event[0].as_f64())
+            .unwrap_or(0.0);
+
+        let meta = &header["kdbg"];
+        println!(
+            "{:<36} {:<20} {:<15} {:<10} {}",
+            id.cyan(),
+            meta["context"].as_str().unwrap_or("-"),
+            meta["namespace"].as_str().unwrap_or("-").bright_black(),
+            meta["user"].as_str().unwrap_or("-"),
+            format_duration(duration)
+        );
+    }
+
+    println!("\nTotal: {} sessions", recordings.len());
+    println!(
+        "{} Use 'kdbg sessions replay <id>' to play one back",
+        "[TIP]".yellow()
+    );
+
+    Ok(())
+}
+
+/// Play a recording back in the terminal
+pub fn replay_session(id: &str, speed: f64, max_idle: f64) -> Result<()> {
+    let recordings = find_recordings()?;
+    let matches: Vec<_> = recordings
+        .iter()
+        .filter(|path| {
+            path.file_stem()
+                .and_then(|s| s.to_str())
+                .is_some_and(|stem| stem.contains(id))
+        })
+        .collect();
+
+    let path = match matches.len() {
+        0 => anyhow::bail!("No sessions found matching '{}'", id),
+        1 => matches[0],
+        _ => {
+            println!("{} Multiple sessions found:", "[INFO]".yellow());
+            for path in &matches {
+                let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+                println!("  - {}", stem.cyan());
+            }
+            anyhow::bail!("Please be more specific");
+        }
+    };
+
+    let file = fs::File::open(path)?;
+    let mut lines = BufReader::new(file).lines();
+    let header: Value = serde_json::from_str(&lines.next().transpose()?.unwrap_or_default())?;
+
+    println!(
+        "{} Replaying {} ({}x{}, speed {}x). Press Ctrl+C to stop",
+        "[INFO]".cyan(),
+        header["title"].as_str().unwrap_or("session").bold(),
+        header["width"],
+        header["height"],
+        speed
+    );
+    println!("{}", "-".repeat(100));
+
+    let mut stdout = io::stdout();
+    let mut previous = 0.0;
+
+    for line in lines {
+        let event: Value = match serde_json::from_str(&line?) {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+        let at = event[0].as_f64().unwrap_or(previous);
+        let delay = ((at - previous) / speed).clamp(0.0, max_idle);
+        previous = at;
+
+        if event[1].as_str() != Some("o") {
+            continue;
+        }
+
+        thread::sleep(Duration::from_secs_f64(delay));
+        stdout.write_all(event[2].as_str().unwrap_or("").as_bytes())?;
+        stdout.flush()?;
+    }
+
+    println!();
+    println!("{}", "-".repeat(100));
+    println!("{} End of recording", "[INFO]".cyan());
+
+    Ok(())
+}
+
+fn find_recordings() -> Result<Vec<PathBuf>> {
+    let dir = get_sessions_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut recordings: Vec<PathBuf> = fs::read_dir(&dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "cast"))
+        .collect();
+
+    // File names start with a timestamp, so this is newest first
+    recordings.sort();
+    recordings.reverse();
+
+    Ok(recordings)
+}
+
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    if seconds < 60 {
+        format!("{}s", seconds)
+    } else if seconds < 3600 {
+        format!("{}m{}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}h{}m", seconds / 3600, (seconds % 3600) / 60)
+    }
+}
@@ -1,48 +1,176 @@
-use crate::kubectl::find_pod;
+use crate::config::Config;
+use crate::kubectl::{find_container, find_pod};
+use crate::recorder::{run_recorded, SessionMeta};
 use anyhow::Result;
 use colored::*;
+use std::io::Write;
 use std::process::{Command, Stdio};
 
-pub fn shell_pod(pod_pattern: &str, namespace: Option<String>) -> Result<()> {
+/// Writes stdin to a new private temp file and prints its path. The file
+/// removes itself once the shell has sourced it.
+const RC_INSTALL: &str = r#"umask 077; f=$(mktemp "${TMPDIR:-/tmp}/.kdbg_rc.XXXXXX") && cat > "$f" && printf "\nrm -f -- '%s'\n" "$f" >> "$f" && echo "$f""#;
+
+/// Injected with `--rc` unless the config provides its own snippet
+const DEFAULT_RC: &str = r#"PS1="[kdbg $KDBG_POD@$KDBG_NAMESPACE] \w \$ "
+alias ll='ls -la'
+alias la='ls -A'
+alias ..='cd ..'
+"#;
+
+pub fn shell_pod(
+    pod_pattern: &str,
+    namespace: Option<String>,
+    container: Option<&str>,
+    shells: &[String],
+    rc: bool,
+    record: bool,
+) -> Result<()> {
     let (pod_name, ns) = find_pod(pod_pattern, namespace)?;
+    let container = container
+        .map(|c| find_container(&pod_name, &ns, c))
+        .transpose()?;
+    let config = Config::load()?;
+
+    let shells = if shells.is_empty() {
+        &config.shell.shells
+    } else {
+        shells
+    };
+
+    let exec = |args: &[&str]| {
+        let mut cmd = Command::new("kubectl");
+        cmd.arg("exec").args(args).args([&pod_name, "-n", &ns]);
+        if let Some(container) = &container {
+            cmd.args(["-c", container]);
+        }
+        cmd.arg("--");
+        cmd
+    };
+
+    // Probe non-interactively so a missing shell doesn't start a session
+    let shell = shells.iter().find(|shell| {
+        exec(&[])
+            .args([shell.as_str(), "-c", "exit 0"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    });
+
+    let Some(shell) = shell else {
+        println!(
+            "{} No shell found in {} (tried {})",
+            "[ERROR]".red(),
+            pod_name.bold(),
+            shells.join(", ")
+        );
+        println!(
+            "{} The image may be distroless. Attach a debug container instead:",
+            "[TIP]".yellow()
+        );
+        println!(
+            "  kdbg debug {} --target {} -n {}",
+            pod_name,
+            container.as_deref().unwrap_or("<container>"),
+            ns
+        );
+        anyhow::bail!("Failed to open shell");
+    };
 
     println!(
-        "{} Opening shell in pod: {} (namespace: {})",
+        "{} Opening {} in pod: {} (namespace: {})",
         "[INFO]".cyan(),
+        shell.yellow(),
         pod_name.bold(),
         ns.bright_black()
     );
     println!("{}", "-".repeat(100));
 
-    let shells = ["/bin/bash", "/bin/sh"];
+    let mut session = exec(&["-it"]);
+    let rc_path = if rc {
+        install_rc(
+            exec(&["-i"]),
+            shell,
+            &pod_name,
+            &ns,
+            config.shell.rc.as_deref().unwrap_or(DEFAULT_RC),
+        )
+    } else {
+        None
+    };
 
-    for (i, shell) in shells.iter().enumerate() {
-        let mut cmd = Command::new("kubectl");
-        cmd.args(["exec", "-it", &pod_name, "-n", &ns, "--", shell]);
+    if let Some(rc_path) = rc_path {
+        // bash skips $ENV, other POSIX shells read it for interactive sessions
+        let start = if shell.ends_with("bash") {
+            format!("exec {} --rcfile {} -i", shell, rc_path)
+        } else {
+            format!("ENV={} exec {} -i", rc_path, shell)
+        };
+        session.args([shell.as_str(), "-c", &start]);
+    } else {
+        session.arg(shell);
+    }
 
-        cmd.stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::null());
+    let status = if record {
+        let meta = SessionMeta {
+            kind: "shell".to_string(),
+            pod: pod_name.clone(),
+            namespace: ns.clone(),
+            container: container.clone(),
+        };
+        run_recorded(session, &meta)?
+    } else {
+        session.status()?
+    };
 
-        let status = cmd.status()?;
+    if !status.success() {
+        anyhow::bail!("Shell exited with error");
+    }
 
-        if status.success() {
-            return Ok(());
-        }
+    Ok(())
+}
 
-        if i == shells.len() - 1 {
-            let mut cmd = Command::new("kubectl");
-            cmd.args(["exec", "-it", &pod_name, "-n", &ns, "--", shell]);
-            cmd.stdin(Stdio::inherit())
-                .stdout(Stdio::inherit())
-                .stderr(Stdio::inherit());
+/// Write the rc snippet into a fresh temp file in the container with the
+/// probed shell, returning its path if that worked
+fn install_rc(
+    mut cmd: Command,
+    shell: &str,
+    pod_name: &str,
+    ns: &str,
+    snippet: &str,
+) -> Option<String> {
+    let rc = format!(
+        "export KDBG_POD='{}'\nexport KDBG_NAMESPACE='{}'\n{}",
+        pod_name, ns, snippet
+    );
+
+    let child = cmd
+        .args([shell, "-c", RC_INSTALL])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn();
 
-            let status = cmd.status()?;
-            if status.success() {
-                return Ok(());
+    let path = child
+        .and_then(|mut child| {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(rc.as_bytes())?;
             }
-        }
+            child.wait_with_output()
+        })
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|path| path.starts_with('/') && !path.contains(char::is_whitespace));
+
+    if path.is_none() {
+        eprintln!(
+            "{} Could not write the rc file (read-only filesystem?), starting a plain shell",
+            "[WARN]".yellow()
+        );
     }
 
-    anyhow::bail!("Failed to open shell (tried bash and sh)")
+    path
 }
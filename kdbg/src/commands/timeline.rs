@@ -0,0 +1,477 @@
+use crate::commands::logs::{container_restarts, pod_containers};
+use crate::kubectl::{event_time, find_pod, find_pods, get_pod_events, get_pod_json};
+use crate::summary::split_timestamp;
+use crate::utils::parse_duration;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local, Utc};
+use colored::*;
+use serde_json::{json, Value};
+use std::process::Command;
+
+#[derive(Clone, Copy)]
+enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// One thing that happened to a pod
+struct Entry {
+    time: DateTime<Utc>,
+    pod: String,
+    /// "pod", "condition", "container", "event" or "log"
+    source: &'static str,
+    severity: Severity,
+    message: String,
+}
+
+/// Merge pod lifecycle, condition transitions, container starts and
+/// terminations, events and (with `grep`) matching log lines of one pod, or
+/// of every pod matching a label selector, into one chronological view
+pub fn show_timeline(
+    target: &str,
+    namespace: Option<String>,
+    since: Option<&str>,
+    grep: Option<&str>,
+    json_output: bool,
+) -> Result<()> {
+    let since = since
+        .map(|s| parse_duration(s).with_context(|| format!("Invalid duration '{}'", s)))
+        .transpose()?;
+
+    // "app=api" style targets are label selectors, anything else a pod pattern
+    let pods = if target.contains('=') {
+        find_pods("", namespace, Some(target))?
+    } else {
+        let (pod_name, ns) = find_pod(target, namespace)?;
+        vec![get_pod_json(&pod_name, &ns)?]
+    };
+
+    let mut entries = Vec::new();
+    for pod in &pods {
+        entries.extend(lifecycle_entries(pod));
+        entries.extend(event_entries(pod)?);
+        if let Some(pattern) = grep {
+            entries.extend(log_entries(pod, pattern, since)?);
+        }
+    }
+
+    // Further back than chrono can go means no cutoff at all
+    let cutoff = since
+        .and_then(|seconds| chrono::TimeDelta::try_seconds(seconds as i64))
+        .and_then(|d| Utc::now().checked_sub_signed(d));
+    merge_entries(&mut entries, cutoff);
+
+    if json_output {
+        let items: Vec<Value> = entries
+            .iter()
+            .map(|e| {
+                json!({
+                    "time": e.time.to_rfc3339(),
+                    "pod": e.pod,
+                    "source": e.source,
+                    "severity": match e.severity {
+                        Severity::Info => "info",
+                        Severity::Warning => "warning",
+                        Severity::Error => "error",
+                    },
+                    "message": e.message,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&items)?);
+        return Ok(());
+    }
+
+    let names: Vec<&str> = pods
+        .iter()
+        .filter_map(|p| p["metadata"]["name"].as_str())
+        .collect();
+    println!(
+        "{} Timeline for {}",
+        "[INFO]".cyan(),
+        if names.len() == 1 {
+            names[0].bold().to_string()
+        } else {
+            format!("{} pods matching {}", names.len(), target.bold())
+        }
+    );
+    println!("{}", "-".repeat(100));
+
+    let multiple = names.len() > 1;
+    for entry in &entries {
+        let source = match entry.source {
+            "event" => entry.source.cyan(),
+            "condition" => entry.source.blue(),
+            "container" => entry.source.magenta(),
+            "log" => entry.source.bright_black(),
+            _ => entry.source.normal(),
+        };
+        let message = match entry.severity {
+            Severity::Info => entry.message.normal(),
+            Severity::Warning => entry.message.yellow(),
+            Severity::Error => entry.message.red(),
+        };
+        let pod = if multiple {
+            format!("{:<30} ", entry.pod)
+        } else {
+            String::new()
+        };
+
+        println!(
+            "{} {:<10} {}{}",
+            entry
+                .time
+                .with_timezone(&Local)
+                .format("%m-%d %H:%M:%S")
+                .to_string()
+                .bright_black(),
+            source,
+            pod.cyan(),
+            message
+        );
+    }
+
+    println!("\nTotal: {} entries", entries.len());
+
+    Ok(())
+}
+
+/// Drop entries before `cutoff` and put the rest in chronological order. The
+/// sort is stable, so entries at the same second keep the order they were
+/// collected in (e.g. "started" before "terminated").
+fn merge_entries(entries: &mut Vec<Entry>, cutoff: Option<DateTime<Utc>>) {
+    if let Some(cutoff) = cutoff {
+        entries.retain(|e| e.time >= cutoff);
+    }
+    entries.sort_by_key(|e| e.time);
+}
+
+/// Creation, deletion, condition transitions and container start/stop times
+/// recorded in the pod's own status
+fn lifecycle_entries(pod: &Value) -> Vec<Entry> {
+    let name = pod["metadata"]["name"].as_str().unwrap_or("unknown");
+    let mut entries = Vec::new();
+    let mut add = |time: Option<DateTime<Utc>>, source, severity, message: String| {
+        if let Some(time) = time {
+            entries.push(Entry {
+                time,
+                pod: name.to_string(),
+                source,
+                severity,
+                message,
+            });
+        }
+    };
+
+    add(
+        parse_time(&pod["metadata"]["creationTimestamp"]),
+        "pod",
+        Severity::Info,
+        "Pod created".to_string(),
+    );
+
+    // deletionTimestamp is when the grace period runs out, not when the
+    // deletion was asked for
+    let deletion = parse_time(&pod["metadata"]["deletionTimestamp"]);
+    match pod["metadata"]["deletionGracePeriodSeconds"]
+        .as_i64()
+        .and_then(chrono::TimeDelta::try_seconds)
+    {
+        Some(grace) => add(
+            deletion.and_then(|t| t.checked_sub_signed(grace)),
+            "pod",
+            Severity::Warning,
+            format!(
+                "Pod deletion requested (grace period {}s)",
+                grace.num_seconds()
+            ),
+        ),
+        None => add(
+            deletion,
+            "pod",
+            Severity::Warning,
+            "Pod scheduled for termination (grace period ends)".to_string(),
+        ),
+    }
+
+    for condition in pod["status"]["conditions"].as_array().into_iter().flatten() {
+        let status = condition["status"].as_str().unwrap_or("Unknown");
+        let mut message = format!(
+            "{} -> {}",
+            condition["type"].as_str().unwrap_or("?"),
+            status
+        );
+        if let Some(reason) = condition["reason"].as_str() {
+            message.push_str(&format!(" ({})", reason));
+        }
+        if let Some(detail) = condition["message"].as_str() {
+            message.push_str(&format!(": {}", detail));
+        }
+        let severity = if status == "True" {
+            Severity::Info
+        } else {
+            Severity::Warning
+        };
+        add(
+            parse_time(&condition["lastTransitionTime"]),
+            "condition",
+            severity,
+            message,
+        );
+    }
+
+    let statuses = ["initContainerStatuses", "containerStatuses"]
+        .iter()
+        .filter_map(|key| pod["status"][key].as_array())
+        .flatten();
+    for status in statuses {
+        let container = status["name"].as_str().unwrap_or("?");
+        for state in [&status["lastState"], &status["state"]] {
+            let running = &state["running"];
+            add(
+                parse_time(&running["startedAt"]),
+                "container",
+                Severity::Info,
+                format!("{} started", container),
+            );
+
+            let terminated = &state["terminated"];
+            if terminated.is_null() {
+                continue;
+            }
+            add(
+                parse_time(&terminated["startedAt"]),
+                "container",
+                Severity::Info,
+                format!("{} started", container),
+            );
+            let exit_code = terminated["exitCode"].as_i64().unwrap_or(0);
+            let mut message = format!(
+                "{} terminated: {} (exit {})",
+                container,
+                terminated["reason"].as_str().unwrap_or("Unknown"),
+                exit_code
+            );
+            if let Some(detail) = terminated["message"].as_str() {
+                message.push_str(&format!(": {}", detail.trim()));
+            }
+            let severity = if exit_code == 0 {
+                Severity::Info
+            } else {
+                Severity::Error
+            };
+            add(
+                parse_time(&terminated["finishedAt"]),
+                "container",
+                severity,
+                message,
+            );
+        }
+    }
+
+    entries
+}
+
+fn event_entries(pod: &Value) -> Result<Vec<Entry>> {
+    let name = pod["metadata"]["name"].as_str().unwrap_or("unknown");
+    let ns = pod["metadata"]["namespace"].as_str().unwrap_or("default");
+
+    Ok(get_pod_events(name, ns)?
+        .iter()
+        .filter_map(|event| event_entry(name, event))
+        .collect())
+}
+
+fn event_entry(pod: &str, event: &Value) -> Option<Entry> {
+    let time = parse_time(&json!(event_time(event)))?;
+    let mut message = format!(
+        "{}: {}",
+        event["reason"].as_str().unwrap_or("-"),
+        event["message"].as_str().unwrap_or("").trim()
+    );
+    if let Some(count) = event["count"].as_u64().filter(|c| *c > 1) {
+        message.push_str(&format!(" (x{})", count));
+    }
+    Some(Entry {
+        time,
+        pod: pod.to_string(),
+        source: "event",
+        severity: if event["type"].as_str() == Some("Warning") {
+            Severity::Warning
+        } else {
+            Severity::Info
+        },
+        message,
+    })
+}
+
+/// Lines of each container's log searched for `--grep`, so a chatty
+/// container's whole history is never pulled
+const LOG_TAIL: usize = 10000;
+
+/// Log lines containing `pattern` (case-insensitive), from every container
+/// and from the previous instance of containers that restarted
+fn log_entries(pod: &Value, pattern: &str, since: Option<u64>) -> Result<Vec<Entry>> {
+    let name = pod["metadata"]["name"].as_str().unwrap_or("unknown");
+    let ns = pod["metadata"]["namespace"].as_str().unwrap_or("default");
+    let pattern = pattern.to_lowercase();
+    let mut entries = Vec::new();
+
+    for container in pod_containers(pod) {
+        let cname = container["name"].as_str().unwrap_or("unknown");
+        let restarted = container_restarts(pod, cname) > 0;
+
+        for previous in [false, true].into_iter().filter(|p| !p || restarted) {
+            let mut cmd = Command::new("kubectl");
+            cmd.args(["logs", name, "-n", ns, "-c", cname, "--timestamps"])
+                .arg(format!("--tail={}", LOG_TAIL));
+            if previous {
+                cmd.arg("--previous");
+            }
+            if let Some(seconds) = since {
+                cmd.arg(format!("--since={}s", seconds));
+            }
+
+            // Containers that never started have no logs; that's not an error
+            let output = cmd.output()?;
+            if !output.status.success() {
+                continue;
+            }
+
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                let (Some(time), text) = split_timestamp(line) else {
+                    continue;
+                };
+                if text.to_lowercase().contains(&pattern) {
+                    entries.push(Entry {
+                        time,
+                        pod: name.to_string(),
+                        source: "log",
+                        severity: Severity::Info,
+                        message: format!("{}: {}", cname, text.trim()),
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+fn parse_time(value: &Value) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.as_str()?)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> Value {
+        let path = format!(
+            "{}/tests/fixtures/timeline/{}.json",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        serde_json::from_str(&std::fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|e| panic!("{}: {}", path, e))
+    }
+
+    /// The fixture's lifecycle and events merged as `show_timeline` does
+    fn merged(fixture: &Value, cutoff: Option<&str>) -> Vec<Entry> {
+        let mut entries = lifecycle_entries(&fixture["pod"]);
+        entries.extend(
+            fixture["events"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter_map(|event| event_entry("api-abc-1", event)),
+        );
+        let cutoff = cutoff.map(|t| DateTime::parse_from_rfc3339(t).unwrap().with_timezone(&Utc));
+        merge_entries(&mut entries, cutoff);
+        entries
+    }
+
+    fn describe(entries: &[Entry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|e| {
+                format!(
+                    "{} {} {}",
+                    e.time.format("%d %H:%M:%S"),
+                    e.source,
+                    e.message
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lifecycle_covers_pod_conditions_and_containers() {
+        let entries = lifecycle_entries(&fixture("oom_then_deleted")["pod"]);
+
+        assert_eq!(
+            describe(&entries),
+            [
+                "18 22:15:24 pod Pod created",
+                "19 03:20:00 pod Pod deletion requested (grace period 30s)",
+                "18 22:15:24 condition PodScheduled -> True",
+                "19 03:12:24 condition Ready -> False (ContainersNotReady): containers with unready status: [app]",
+                "19 03:05:24 container app started",
+                "19 03:12:24 container app terminated: OOMKilled (exit 137)",
+                "19 03:13:24 container app started",
+            ]
+        );
+        assert!(matches!(entries[5].severity, Severity::Error));
+        assert!(matches!(entries[3].severity, Severity::Warning));
+    }
+
+    #[test]
+    fn deletion_without_grace_period_is_labelled_as_its_deadline() {
+        let mut pod = fixture("oom_then_deleted")["pod"].clone();
+        pod["metadata"]
+            .as_object_mut()
+            .unwrap()
+            .remove("deletionGracePeriodSeconds");
+
+        let entries = lifecycle_entries(&pod);
+        assert_eq!(
+            describe(&entries[1..2]),
+            ["19 03:20:30 pod Pod scheduled for termination (grace period ends)"]
+        );
+    }
+
+    #[test]
+    fn merged_timeline_is_chronological() {
+        assert_eq!(
+            describe(&merged(&fixture("oom_then_deleted"), None)),
+            [
+                "18 22:15:24 pod Pod created",
+                "18 22:15:24 condition PodScheduled -> True",
+                "18 22:15:24 event Scheduled: Successfully assigned prod/api-abc-1 to node-1",
+                "19 03:05:24 container app started",
+                "19 03:12:24 condition Ready -> False (ContainersNotReady): containers with unready status: [app]",
+                "19 03:12:24 container app terminated: OOMKilled (exit 137)",
+                "19 03:12:50 event BackOff: Back-off restarting failed container app (x3)",
+                "19 03:13:24 container app started",
+                "19 03:20:00 pod Pod deletion requested (grace period 30s)",
+                "19 03:20:00 event Killing: Stopping container app",
+            ]
+        );
+    }
+
+    #[test]
+    fn cutoff_drops_older_entries() {
+        let entries = merged(&fixture("oom_then_deleted"), Some("2026-10-19T03:13:00Z"));
+        assert_eq!(
+            describe(&entries),
+            [
+                "19 03:13:24 container app started",
+                "19 03:20:00 pod Pod deletion requested (grace period 30s)",
+                "19 03:20:00 event Killing: Stopping container app",
+            ]
+        );
+    }
+}
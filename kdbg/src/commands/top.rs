@@ -1,29 +1,327 @@
+use crate::commands::nodes::node_status;
+use crate::kubectl::{get_nodes_json, get_pods_json};
+use crate::quantity::{format_cpu, format_memory, parse_cpu_millis, parse_memory_bytes};
 use anyhow::Result;
 use colored::*;
+use serde_json::Value;
+use std::cmp::Ordering;
 use std::process::Command;
 
-pub fn show_top(namespace: Option<String>) -> Result<()> {
-    let mut args = vec!["top", "pods"];
+/// Memory-limit utilization (%) at which rows turn yellow / red
+const MEMORY_WARN: f64 = 75.0;
+const MEMORY_CRITICAL: f64 = 90.0;
 
-    let ns_str;
-    if let Some(ns) = &namespace {
-        ns_str = ns.clone();
-        args.extend(&["-n", &ns_str]);
-    } else {
-        args.push("--all-namespaces");
+/// Usage and configured resources for one container
+struct ContainerUsage {
+    name: String,
+    cpu: f64,
+    memory: f64,
+    cpu_request: Option<f64>,
+    cpu_limit: Option<f64>,
+    memory_request: Option<f64>,
+    memory_limit: Option<f64>,
+}
+
+struct PodUsage {
+    name: String,
+    namespace: String,
+    containers: Vec<ContainerUsage>,
+}
+
+impl PodUsage {
+    fn cpu(&self) -> f64 {
+        self.containers.iter().map(|c| c.cpu).sum()
+    }
+
+    fn memory(&self) -> f64 {
+        self.containers.iter().map(|c| c.memory).sum()
+    }
+
+    /// Pod-level request/limit, only defined when every container sets it
+    fn total(&self, field: fn(&ContainerUsage) -> Option<f64>) -> Option<f64> {
+        self.containers.iter().map(field).sum()
+    }
+
+    fn cpu_limit_pct(&self) -> Option<f64> {
+        percent(self.cpu(), self.total(|c| c.cpu_limit))
+    }
+
+    fn memory_limit_pct(&self) -> Option<f64> {
+        percent(self.memory(), self.total(|c| c.memory_limit))
+    }
+}
+
+pub fn show_top(namespace: Option<String>, sort_by: Option<&str>, containers: bool) -> Result<()> {
+    let path = match &namespace {
+        Some(ns) => format!("/apis/metrics.k8s.io/v1beta1/namespaces/{}/pods", ns),
+        None => "/apis/metrics.k8s.io/v1beta1/pods".to_string(),
+    };
+
+    let output = Command::new("kubectl")
+        .args(["get", "--raw", &path])
+        .output()?;
+
+    if !output.status.success() {
+        eprintln!(
+            "{} Failed to get resource usage (metrics-server may not be installed)",
+            "[WARN]".yellow()
+        );
+        return Ok(());
+    }
+
+    let metrics: Value = serde_json::from_slice(&output.stdout)?;
+    let pods = get_pods_json(namespace)?;
+    let mut usage = join_usage(&metrics, &pods);
+
+    match sort_by {
+        Some("cpu") => usage.sort_by(|a, b| desc(Some(a.cpu()), Some(b.cpu()))),
+        Some("memory") => usage.sort_by(|a, b| desc(Some(a.memory()), Some(b.memory()))),
+        Some("cpu-limit%") => usage.sort_by(|a, b| desc(a.cpu_limit_pct(), b.cpu_limit_pct())),
+        Some("memory-limit%") => {
+            usage.sort_by(|a, b| desc(a.memory_limit_pct(), b.memory_limit_pct()))
+        }
+        _ => usage.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name))),
     }
 
     println!("{}", "Pod Resource Usage:".cyan().bold());
     println!("{}", "-".repeat(100));
+    println!(
+        "{:<40} {:<15} {:>7} {:>6} {:>6} {:>8} {:>6} {:>6}",
+        "NAME".bold(),
+        "NAMESPACE".bold(),
+        "CPU".bold(),
+        "REQ%".bold(),
+        "LIM%".bold(),
+        "MEMORY".bold(),
+        "REQ%".bold(),
+        "LIM%".bold()
+    );
+    println!("{}", "-".repeat(100).bright_black());
 
-    let status = Command::new("kubectl").args(&args).status()?;
+    let mut near_limit = 0;
+    for pod in &usage {
+        let memory_limit_pct = pod.memory_limit_pct();
+        let name = match memory_limit_pct {
+            Some(pct) if pct >= MEMORY_CRITICAL => pod.name.red().bold(),
+            Some(pct) if pct >= MEMORY_WARN => pod.name.yellow(),
+            _ => pod.name.cyan(),
+        };
+        if memory_limit_pct.is_some_and(|pct| pct >= MEMORY_WARN) {
+            near_limit += 1;
+        }
 
-    if !status.success() {
+        println!(
+            "{:<40} {:<15} {:>7} {:>6} {:>6} {:>8} {:>6} {:>6}",
+            name,
+            pod.namespace.bright_black(),
+            format_cpu(pod.cpu()),
+            pct_str(percent(pod.cpu(), pod.total(|c| c.cpu_request))),
+            pct_str(pod.cpu_limit_pct()),
+            format_memory(pod.memory()),
+            pct_str(percent(pod.memory(), pod.total(|c| c.memory_request))),
+            memory_pct_colored(memory_limit_pct)
+        );
+
+        if containers {
+            for container in &pod.containers {
+                println!(
+                    "  {:<38} {:<15} {:>7} {:>6} {:>6} {:>8} {:>6} {:>6}",
+                    format!("└ {}", container.name).bright_black(),
+                    "",
+                    format_cpu(container.cpu),
+                    pct_str(percent(container.cpu, container.cpu_request)),
+                    pct_str(percent(container.cpu, container.cpu_limit)),
+                    format_memory(container.memory),
+                    pct_str(percent(container.memory, container.memory_request)),
+                    memory_pct_colored(percent(container.memory, container.memory_limit))
+                );
+            }
+        }
+    }
+
+    println!("\nTotal: {} pods", usage.len());
+    if near_limit > 0 {
+        println!(
+            "{} {} pods are above {}% of their memory limit",
+            "[WARN]".yellow(),
+            near_limit,
+            MEMORY_WARN
+        );
+    }
+
+    Ok(())
+}
+
+/// Node usage against allocatable, with bars
+pub fn show_top_nodes() -> Result<()> {
+    let output = Command::new("kubectl")
+        .args(["get", "--raw", "/apis/metrics.k8s.io/v1beta1/nodes"])
+        .output()?;
+
+    if !output.status.success() {
         eprintln!(
             "{} Failed to get resource usage (metrics-server may not be installed)",
             "[WARN]".yellow()
         );
+        return Ok(());
+    }
+
+    let metrics: Value = serde_json::from_slice(&output.stdout)?;
+    let nodes = get_nodes_json()?;
+    let empty_vec = vec![];
+    let nodes = nodes["items"].as_array().unwrap_or(&empty_vec);
+
+    println!("{}", "Node Resource Usage:".cyan().bold());
+    println!("{}", "-".repeat(100));
+    println!(
+        "{:<30} {:<10} {:>7} {:<27} {:>8} {:<27}",
+        "NAME".bold(),
+        "STATUS".bold(),
+        "CPU".bold(),
+        "",
+        "MEMORY".bold(),
+        ""
+    );
+    println!("{}", "-".repeat(100).bright_black());
+
+    for item in metrics["items"].as_array().unwrap_or(&empty_vec) {
+        let name = item["metadata"]["name"].as_str().unwrap_or("unknown");
+        let node = nodes
+            .iter()
+            .find(|n| n["metadata"]["name"].as_str() == Some(name))
+            .unwrap_or(&Value::Null);
+        let allocatable = &node["status"]["allocatable"];
+
+        let cpu = item["usage"]["cpu"]
+            .as_str()
+            .and_then(parse_cpu_millis)
+            .unwrap_or(0.0);
+        let memory = item["usage"]["memory"]
+            .as_str()
+            .and_then(parse_memory_bytes)
+            .unwrap_or(0.0);
+        let cpu_pct = percent(cpu, allocatable["cpu"].as_str().and_then(parse_cpu_millis));
+        let memory_pct = percent(
+            memory,
+            allocatable["memory"].as_str().and_then(parse_memory_bytes),
+        );
+
+        println!(
+            "{:<30} {:<10} {:>7} {} {:>8} {}",
+            name.cyan(),
+            node_status(node),
+            format_cpu(cpu),
+            usage_bar(cpu_pct),
+            format_memory(memory),
+            usage_bar(memory_pct)
+        );
     }
 
     Ok(())
 }
+
+/// "[██████░░░░░░░░░░░░░░]  30%" colored by how close it is to full
+fn usage_bar(pct: Option<f64>) -> String {
+    const WIDTH: usize = 20;
+    let Some(pct) = pct else {
+        return format!("[{}]    -", " ".repeat(WIDTH));
+    };
+
+    let filled = ((pct / 100.0) * WIDTH as f64)
+        .round()
+        .clamp(0.0, WIDTH as f64) as usize;
+    let bar = format!("{}{}", "█".repeat(filled), "░".repeat(WIDTH - filled));
+    let bar = if pct >= MEMORY_CRITICAL {
+        bar.red()
+    } else if pct >= MEMORY_WARN {
+        bar.yellow()
+    } else {
+        bar.green()
+    };
+
+    format!("[{}] {:>3.0}%", bar, pct)
+}
+
+/// Match pod metrics to pod specs to get requests/limits per container
+fn join_usage(metrics: &Value, pods: &Value) -> Vec<PodUsage> {
+    let empty_vec = vec![];
+    let pod_specs = pods["items"].as_array().unwrap_or(&empty_vec);
+
+    metrics["items"]
+        .as_array()
+        .unwrap_or(&empty_vec)
+        .iter()
+        .map(|item| {
+            let name = item["metadata"]["name"].as_str().unwrap_or("unknown");
+            let namespace = item["metadata"]["namespace"].as_str().unwrap_or("default");
+            let spec = pod_specs
+                .iter()
+                .find(|p| {
+                    p["metadata"]["name"].as_str() == Some(name)
+                        && p["metadata"]["namespace"].as_str() == Some(namespace)
+                })
+                .map(|p| &p["spec"])
+                .unwrap_or(&Value::Null);
+
+            let containers = item["containers"]
+                .as_array()
+                .unwrap_or(&empty_vec)
+                .iter()
+                .map(|c| {
+                    let cname = c["name"].as_str().unwrap_or("unknown");
+                    let resources = spec["containers"]
+                        .as_array()
+                        .and_then(|cs| cs.iter().find(|s| s["name"].as_str() == Some(cname)))
+                        .map(|s| &s["resources"])
+                        .unwrap_or(&Value::Null);
+
+                    let cpu = |v: &Value| v.as_str().and_then(parse_cpu_millis);
+                    let memory = |v: &Value| v.as_str().and_then(parse_memory_bytes);
+
+                    ContainerUsage {
+                        name: cname.to_string(),
+                        cpu: cpu(&c["usage"]["cpu"]).unwrap_or(0.0),
+                        memory: memory(&c["usage"]["memory"]).unwrap_or(0.0),
+                        cpu_request: cpu(&resources["requests"]["cpu"]),
+                        cpu_limit: cpu(&resources["limits"]["cpu"]),
+                        memory_request: memory(&resources["requests"]["memory"]),
+                        memory_limit: memory(&resources["limits"]["memory"]),
+                    }
+                })
+                .collect();
+
+            PodUsage {
+                name: name.to_string(),
+                namespace: namespace.to_string(),
+                containers,
+            }
+        })
+        .collect()
+}
+
+fn percent(used: f64, total: Option<f64>) -> Option<f64> {
+    total.filter(|t| *t > 0.0).map(|t| used * 100.0 / t)
+}
+
+/// Descending order with missing values last
+fn desc(a: Option<f64>, b: Option<f64>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => b.partial_cmp(&a).unwrap_or(Ordering::Equal),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn pct_str(pct: Option<f64>) -> String {
+    pct.map(|p| format!("{:.0}%", p))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn memory_pct_colored(pct: Option<f64>) -> ColoredString {
+    match pct {
+        Some(p) if p >= MEMORY_CRITICAL => pct_str(pct).red().bold(),
+        Some(p) if p >= MEMORY_WARN => pct_str(pct).yellow(),
+        _ => pct_str(pct).normal(),
+    }
+}
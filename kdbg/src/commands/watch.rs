@@ -1,122 +1,603 @@
+use crate::alerts::Alerter;
+use crate::config::Config;
+use crate::kubectl::{pod_status, restart_count};
 use crate::utils::calculate_age;
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
 use colored::*;
 use serde_json::Value;
-use std::io::{self, Write};
-use std::process::Command;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-pub fn watch_pods(namespace: Option<String>, interval: u64) -> Result<()> {
-    println!(
-        "{} Watching pods (refresh every {}s, press Ctrl+C to stop)...",
-        "[INFO]".cyan(),
-        interval
-    );
-    println!();
+/// Changes arriving this close together are drawn in one redraw
+const REDRAW_DELAY: Duration = Duration::from_millis(100);
+
+/// Redraw at least this often while changes keep streaming in
+const MAX_REDRAW_DELAY: Duration = Duration::from_secs(1);
+
+/// How long added, changed and deleted pods stay marked in the table
+const HIGHLIGHT_FOR: Duration = Duration::from_secs(10);
+
+/// Lines kept in the "recent changes" log under the table
+const CHANGE_LOG_SIZE: usize = 10;
+
+/// How often time-based alert rules (e.g. Pending for 5m) are re-checked
+const ALERT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest wait between attempts to re-list while the API server is away
+const MAX_RELIST_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Watches failing before delivering anything this many times in a row,
+/// while listing works, are taken as refused (e.g. RBAC allows list only)
+const REFUSED_WATCHES: u32 = 3;
+
+/// Pods by "namespace/name", kept up to date from the watch stream
+type PodTable = BTreeMap<String, Value>;
+
+/// Why a row is highlighted
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    Added,
+    /// Status and/or restart count changed
+    Changed {
+        status: bool,
+        restarts: bool,
+    },
+    Deleted,
+}
+
+/// Pod table plus what changed recently
+struct WatchState {
+    pods: PodTable,
+    /// Highlighted rows and when they were marked
+    marks: BTreeMap<String, (Mark, Instant)>,
+    /// Last known state of recently deleted pods, shown until unmarked
+    deleted: PodTable,
+    changes: VecDeque<(DateTime<Local>, String)>,
+    alerts: Alerter,
+    /// None until the first check, so the initial one isn't rate-limited
+    last_alert_check: Option<Instant>,
+}
+
+/// Live pod table fed by the API server's watch stream: one list, then only
+/// changes. Redraws when something changes; re-lists when the stream's
+/// resourceVersion has expired. Alert rules from ~/.kdbg/config.json are
+/// checked on every change.
+pub fn watch_pods(namespace: Option<String>) -> Result<()> {
+    let config = Config::load()?;
+    let mut alerts = Some(Alerter::new(&config.watch.alerts)?);
+
+    let path = match &namespace {
+        Some(ns) => format!("/api/v1/namespaces/{}/pods", ns),
+        None => "/api/v1/pods".to_string(),
+    };
+
+    let mut state: Option<WatchState> = None;
+    let mut backoff = Duration::from_secs(1);
+    let mut refused_watches = 0;
 
     loop {
+        let (pods, mut resource_version) = match (list_pods(&path), &mut state) {
+            (Ok(listed), _) => listed,
+            // Nothing to show yet, so a first list that fails is an error
+            (Err(err), None) => return Err(err),
+            // Otherwise keep the last known table up while the API server
+            // is away (restart, VPN blip) and try again
+            (Err(err), Some(state)) => {
+                state.log(format!(
+                    "{} {}, retrying in {}s",
+                    "!".yellow(),
+                    err,
+                    backoff.as_secs()
+                ));
+                state.draw()?;
+                thread::sleep(backoff);
+                backoff = (backoff * 2).min(MAX_RELIST_BACKOFF);
+                continue;
+            }
+        };
+        backoff = Duration::from_secs(1);
+        let state = match &mut state {
+            // After a re-list, diff against what we had so nothing that
+            // happened while the stream was down goes unnoticed
+            Some(state) => {
+                state.resync(pods);
+                state
+            }
+            None => state.insert(WatchState::new(
+                pods,
+                alerts.take().context("alert rules already in use")?,
+            )),
+        };
+        state.draw()?;
+        let mut last_draw = Instant::now();
+
+        // Watch from where the list left off until the server says that
+        // point is too old (410 Gone), then start over with a fresh list
+        'watch: loop {
+            let (mut child, events, stderr) = start_watch(&path, &resource_version)?;
+            let mut changed = false;
+            let mut received = false;
+
+            loop {
+                let event = match events.recv_timeout(REDRAW_DELAY) {
+                    Ok(event) => event,
+                    Err(RecvTimeoutError::Timeout) => {
+                        // Expiring highlights and time-based alerts change
+                        // what's shown too
+                        changed |= state.check_alerts();
+                        if changed || state.expire_marks() {
+                            state.draw()?;
+                            last_draw = Instant::now();
+                            changed = false;
+                        }
+                        continue;
+                    }
+                    // Streams are closed by the server every few minutes;
+                    // resume from the last version we saw
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+
+                received = true;
+                let object = &event["object"];
+                match event["type"].as_str() {
+                    Some("ADDED") | Some("MODIFIED") => {
+                        changed |= state.update(pod_key(object), Some(object.clone()));
+                    }
+                    Some("DELETED") => {
+                        changed |= state.update(pod_key(object), None);
+                    }
+                    Some("ERROR") => {
+                        let _ = child.kill();
+                        let _ = child.wait();
+                        if object["code"].as_u64() == Some(410) {
+                            break 'watch;
+                        }
+                        anyhow::bail!(
+                            "Watch failed: {}",
+                            object["message"].as_str().unwrap_or("unknown error")
+                        );
+                    }
+                    _ => {}
+                }
+                if let Some(version) = object["metadata"]["resourceVersion"].as_str() {
+                    resource_version = version.to_string();
+                }
+                // A busy stream may never leave a gap for the timeout branch,
+                // so time-based rules are checked here too
+                changed |= state.check_alerts();
+                if changed && last_draw.elapsed() >= MAX_REDRAW_DELAY {
+                    state.draw()?;
+                    last_draw = Instant::now();
+                    changed = false;
+                }
+            }
+
+            if changed {
+                state.draw()?;
+                last_draw = Instant::now();
+            }
+            if !child.wait()?.success() {
+                let error = stderr.join().unwrap_or_default();
+                refused_watches = if received { 0 } else { refused_watches + 1 };
+                // Re-listing won't fix a watch the server refuses outright
+                if error.to_lowercase().contains("forbidden") || refused_watches >= REFUSED_WATCHES
+                {
+                    anyhow::bail!("Watch failed: {}", error.trim());
+                }
+                // e.g. connection lost: the version may be stale, re-list
+                thread::sleep(Duration::from_secs(1));
+                break 'watch;
+            }
+            if received {
+                refused_watches = 0;
+            }
+        }
+    }
+}
+
+impl WatchState {
+    fn new(pods: PodTable, alerts: Alerter) -> Self {
+        let mut state = WatchState {
+            pods,
+            marks: BTreeMap::new(),
+            deleted: PodTable::new(),
+            changes: VecDeque::new(),
+            alerts,
+            last_alert_check: None,
+        };
+        state.check_alerts();
+        state
+    }
+
+    /// Apply a new version of a pod (None: deleted). Returns whether
+    /// anything shown changed.
+    fn update(&mut self, key: String, pod: Option<Value>) -> bool {
+        let alerted = match &pod {
+            Some(pod) => {
+                let fired = self.alerts.check(&key, pod);
+                self.log_alerts(fired)
+            }
+            None => {
+                self.alerts.forget(&key);
+                false
+            }
+        };
+
+        let old = self
+            .pods
+            .get(&key)
+            .map(|old| (pod_status(old), restart_count(old)));
+        let mark = match (old, &pod) {
+            (None, None) => return alerted,
+            (None, Some(new)) => {
+                self.log(format!(
+                    "{} {} added ({})",
+                    "+".green(),
+                    key,
+                    pod_status(new)
+                ));
+                Mark::Added
+            }
+            (Some(_), None) => {
+                self.log(format!("{} {} deleted", "-".red(), key));
+                Mark::Deleted
+            }
+            (Some((old_status, old_restarts)), Some(new)) => {
+                let (new_status, new_restarts) = (pod_status(new), restart_count(new));
+                if old_status == new_status && old_restarts == new_restarts {
+                    self.pods.insert(key, new.clone());
+                    return alerted;
+                }
+
+                let mut what = Vec::new();
+                if old_status != new_status {
+                    what.push(format!("{} -> {}", old_status, new_status));
+                }
+                if old_restarts != new_restarts {
+                    what.push(format!("restarts {} -> {}", old_restarts, new_restarts));
+                }
+                self.log(format!("{} {} {}", "~".yellow(), key, what.join(", ")));
+
+                // A pod added moments ago stays marked as new
+                match self.marks.get(&key) {
+                    Some((Mark::Added, _)) => Mark::Added,
+                    _ => Mark::Changed {
+                        status: old_status != new_status,
+                        restarts: old_restarts != new_restarts,
+                    },
+                }
+            }
+        };
+
+        match pod {
+            Some(pod) => {
+                self.deleted.remove(&key);
+                self.pods.insert(key.clone(), pod);
+            }
+            None => {
+                if let Some(old) = self.pods.remove(&key) {
+                    self.deleted.insert(key.clone(), old);
+                }
+            }
+        }
+        self.marks.insert(key, (mark, Instant::now()));
+
+        true
+    }
+
+    /// Replace the table with a fresh list, recording the differences
+    fn resync(&mut self, pods: PodTable) {
+        let gone: Vec<String> = self
+            .pods
+            .keys()
+            .filter(|key| !pods.contains_key(*key))
+            .cloned()
+            .collect();
+        for key in gone {
+            self.update(key, None);
+        }
+        for (key, pod) in pods {
+            self.update(key, Some(pod));
+        }
+    }
+
+    /// Re-check all pods against the alert rules (at most every
+    /// ALERT_CHECK_INTERVAL) and collect hook results. Returns whether
+    /// anything was logged.
+    fn check_alerts(&mut self) -> bool {
+        let mut logged = self.log_alert_results();
+        let recent = self
+            .last_alert_check
+            .is_some_and(|t| t.elapsed() < ALERT_CHECK_INTERVAL);
+        if self.alerts.is_empty() || recent {
+            return logged;
+        }
+        self.last_alert_check = Some(Instant::now());
+
+        let mut fired = Vec::new();
+        for (key, pod) in &self.pods {
+            fired.extend(self.alerts.check(key, pod));
+        }
+        logged |= self.log_alerts(fired);
+        logged
+    }
+
+    fn log_alerts(&mut self, fired: Vec<String>) -> bool {
+        let any = !fired.is_empty();
+        for alert in fired {
+            self.log(format!("{} {}", "!".red().bold(), alert.red()));
+        }
+        any
+    }
+
+    fn log_alert_results(&mut self) -> bool {
+        let results = self.alerts.results();
+        let any = !results.is_empty();
+        for result in results {
+            self.log(format!("{} {}", "!".bright_black(), result.bright_black()));
+        }
+        any
+    }
+
+    /// Drop highlights older than HIGHLIGHT_FOR. Returns whether any expired.
+    fn expire_marks(&mut self) -> bool {
+        let expired: Vec<String> = self
+            .marks
+            .iter()
+            .filter(|(_, (_, since))| since.elapsed() >= HIGHLIGHT_FOR)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in &expired {
+            self.marks.remove(key);
+            self.deleted.remove(key);
+        }
+
+        !expired.is_empty()
+    }
+
+    fn log(&mut self, change: String) {
+        self.changes.push_back((Local::now(), change));
+        while self.changes.len() > CHANGE_LOG_SIZE {
+            self.changes.pop_front();
+        }
+    }
+
+    fn draw(&self) -> Result<()> {
         print!("\x1B[2J\x1B[1;1H");
         io::stdout().flush()?;
 
         println!("{}", "=".repeat(100).bright_black());
         println!(
-            "{} {} {}",
+            "{} {} {} {}",
             "kdbg watch".cyan().bold(),
             "-".bright_black(),
-            chrono::Local::now()
+            Local::now()
                 .format("%Y-%m-%d %H:%M:%S")
                 .to_string()
-                .bright_black()
+                .bright_black(),
+            "(live, press Ctrl+C to stop)".bright_black()
         );
+        if !self.alerts.is_empty() {
+            println!(
+                "{}",
+                format!("{} alert rules active", self.alerts.len()).bright_black()
+            );
+        }
         println!("{}", "=".repeat(100).bright_black());
         println!();
 
-        let mut args = vec!["get", "pods"];
+        let mut running = 0;
+        let mut pending = 0;
+        let mut failed = 0;
+        let mut other = 0;
 
-        let ns_str;
-        if let Some(ns) = &namespace {
-            ns_str = ns.clone();
-            args.extend(&["-n", &ns_str]);
-        } else {
-            args.push("--all-namespaces");
+        for pod in self.pods.values() {
+            match pod["status"]["phase"].as_str().unwrap_or("Unknown") {
+                "Running" => running += 1,
+                "Pending" => pending += 1,
+                "Failed" => failed += 1,
+                _ => other += 1,
+            }
         }
 
-        args.extend(&["-o", "json"]);
+        println!(
+            "Total: {} | {}: {} | {}: {} | {}: {} | {}: {}",
+            self.pods.len().to_string().bold(),
+            "Running".green(),
+            running,
+            "Pending".yellow(),
+            pending,
+            "Failed".red(),
+            failed,
+            "Other".bright_black(),
+            other
+        );
+        println!();
 
-        let output = Command::new("kubectl").args(&args).output()?;
+        println!(
+            "  {:<48} {:<20} {:<20} {:<10} {}",
+            "NAME".bold(),
+            "NAMESPACE".bold(),
+            "STATUS".bold(),
+            "RESTARTS".bold(),
+            "AGE".bold()
+        );
+        println!("{}", "-".repeat(100).bright_black());
 
-        if output.status.success() {
-            let json: Value = serde_json::from_slice(&output.stdout)?;
-            let empty_vec = vec![];
-            let pods = json["items"].as_array().unwrap_or(&empty_vec);
+        // Deleted pods stay in place (dimmed) for a moment
+        let mut rows: Vec<(&String, &Value)> =
+            self.pods.iter().chain(self.deleted.iter()).collect();
+        rows.sort_by(|a, b| a.0.cmp(b.0));
 
-            let mut running = 0;
-            let mut pending = 0;
-            let mut failed = 0;
-            let mut other = 0;
+        for (key, pod) in rows {
+            let mark = self.marks.get(key).map(|(mark, _)| *mark);
+            self.draw_row(pod, mark);
+        }
 
-            for pod in pods {
-                match pod["status"]["phase"].as_str().unwrap_or("Unknown") {
-                    "Running" => running += 1,
-                    "Pending" => pending += 1,
-                    "Failed" => failed += 1,
-                    _ => other += 1,
-                }
+        if !self.changes.is_empty() {
+            println!();
+            println!("{}", "Recent changes:".cyan().bold());
+            println!("{}", "-".repeat(100).bright_black());
+            for (time, change) in self.changes.iter().rev() {
+                println!(
+                    "{} {}",
+                    time.format("%H:%M:%S").to_string().bright_black(),
+                    change
+                );
             }
+        }
 
-            println!(
-                "Total: {} | {}: {} | {}: {} | {}: {} | {}: {}",
-                pods.len().to_string().bold(),
-                "Running".green(),
-                running,
-                "Pending".yellow(),
-                pending,
-                "Failed".red(),
-                failed,
-                "Other".bright_black(),
-                other
-            );
-            println!();
+        Ok(())
+    }
 
+    fn draw_row(&self, pod: &Value, mark: Option<Mark>) {
+        let name = pod["metadata"]["name"].as_str().unwrap_or("unknown");
+        let ns = pod["metadata"]["namespace"].as_str().unwrap_or("default");
+        let created = pod["metadata"]["creationTimestamp"].as_str().unwrap_or("");
+        let status = pod_status(pod);
+        let restarts = restart_count(pod).to_string();
+
+        if mark == Some(Mark::Deleted) {
             println!(
-                "{:<50} {:<20} {:<15} {:<10} {}",
-                "NAME".bold(),
-                "NAMESPACE".bold(),
-                "STATUS".bold(),
-                "RESTARTS".bold(),
-                "AGE".bold()
+                "{} {}",
+                "-".red().bold(),
+                format!(
+                    "{:<48} {:<20} {:<20} {:<10} {}",
+                    name,
+                    ns,
+                    "Deleted",
+                    restarts,
+                    calculate_age(created)
+                )
+                .bright_black()
+                .strikethrough()
             );
-            println!("{}", "-".repeat(100).bright_black());
+            return;
+        }
 
-            for pod in pods {
-                let name = pod["metadata"]["name"].as_str().unwrap_or("unknown");
-                let ns = pod["metadata"]["namespace"].as_str().unwrap_or("default");
-                let status = pod["status"]["phase"].as_str().unwrap_or("Unknown");
-                let restarts = pod["status"]["containerStatuses"]
-                    .as_array()
-                    .and_then(|arr| arr.first())
-                    .and_then(|c| c["restartCount"].as_u64())
-                    .unwrap_or(0);
-                let created = pod["metadata"]["creationTimestamp"].as_str().unwrap_or("");
-                let age = calculate_age(created);
-
-                let status_colored = match status {
-                    "Running" => status.green(),
-                    "Pending" => status.yellow(),
-                    "Failed" => status.red(),
-                    _ => status.bright_black(),
-                };
+        let mut status_colored = match status.as_str() {
+            "Running" => status.green(),
+            "Completed" | "Succeeded" => status.blue(),
+            "Pending" | "ContainerCreating" | "PodInitializing" | "Terminating" => status.yellow(),
+            s if s.starts_with("Init:") && !s.contains("Err") && !s.contains("BackOff") => {
+                status.yellow()
+            }
+            _ => status.red(),
+        };
+        let mut restarts_colored = restarts.normal();
+        let mut name_colored = name.normal();
 
-                println!(
-                    "{:<50} {:<20} {:<15} {:<10} {}",
-                    name, ns, status_colored, restarts, age
-                );
+        let marker = match mark {
+            Some(Mark::Added) => {
+                name_colored = name_colored.green().bold();
+                "+".green().bold()
             }
-        } else {
-            println!("{} Failed to get pods", "[ERROR]".red());
-        }
+            Some(Mark::Changed { status, restarts }) => {
+                name_colored = name_colored.bold();
+                if status {
+                    status_colored = status_colored.bold().reversed();
+                }
+                if restarts {
+                    restarts_colored = restarts_colored.yellow().bold().reversed();
+                }
+                "~".yellow().bold()
+            }
+            _ => " ".normal(),
+        };
+
+        println!(
+            "{} {:<48} {:<20} {:<20} {:<10} {}",
+            marker,
+            name_colored,
+            ns,
+            status_colored,
+            restarts_colored,
+            calculate_age(created)
+        );
+    }
+}
 
-        thread::sleep(Duration::from_secs(interval));
+fn list_pods(path: &str) -> Result<(PodTable, String)> {
+    let output = Command::new("kubectl")
+        .args(["get", "--raw", path])
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to get pods: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
     }
+
+    let json: Value = serde_json::from_slice(&output.stdout)?;
+    let pods = json["items"]
+        .as_array()
+        .map(|items| items.iter().map(|p| (pod_key(p), p.clone())).collect())
+        .unwrap_or_default();
+    let resource_version = json["metadata"]["resourceVersion"]
+        .as_str()
+        .unwrap_or("")
+        .to_string();
+
+    Ok((pods, resource_version))
+}
+
+/// Start `kubectl get --raw <path>?watch=1` and hand each watch event
+/// ({"type": ..., "object": ...}) to the returned channel. The handle yields
+/// kubectl's stderr once it exits.
+fn start_watch(
+    path: &str,
+    resource_version: &str,
+) -> Result<(Child, Receiver<Value>, thread::JoinHandle<String>)> {
+    let mut child = Command::new("kubectl")
+        .args([
+            "get",
+            "--raw",
+            &format!(
+                "{}?watch=1&allowWatchBookmarks=true&resourceVersion={}",
+                path, resource_version
+            ),
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = child
+        .stdout
+        .take()
+        .context("Failed to read kubectl output")?;
+    let stderr = child.stderr.take();
+
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            if let Ok(event) = serde_json::from_str::<Value>(&line) {
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    let stderr = thread::spawn(move || {
+        let mut buf = String::new();
+        if let Some(mut stderr) = stderr {
+            let _ = stderr.read_to_string(&mut buf);
+        }
+        buf
+    });
+
+    Ok((child, rx, stderr))
+}
+
+fn pod_key(pod: &Value) -> String {
+    format!(
+        "{}/{}",
+        pod["metadata"]["namespace"].as_str().unwrap_or("default"),
+        pod["metadata"]["name"].as_str().unwrap_or("unknown")
+    )
 }
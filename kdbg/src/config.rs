@@ -0,0 +1,219 @@
+use crate::utils::get_kdbg_dir;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+
+/// User configuration loaded from ~/.kdbg/config.json
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub shell: ShellConfig,
+    pub forward: ForwardConfig,
+    pub debug: DebugConfig,
+    pub watch: WatchConfig,
+}
+
+/// Settings for `kdbg shell`
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ShellConfig {
+    /// Shells to probe, in order of preference
+    pub shells: Vec<String>,
+    /// Replaces the built-in rc snippet injected with `--rc`
+    pub rc: Option<String>,
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        Self {
+            shells: vec![
+                "/bin/bash".to_string(),
+                "/bin/ash".to_string(),
+                "/bin/sh".to_string(),
+            ],
+            rc: None,
+        }
+    }
+}
+
+/// Settings for `kdbg forward`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ForwardConfig {
+    /// Named sets of forwards started together with `kdbg forward up <name>`
+    pub profiles: BTreeMap<String, Vec<ForwardSpec>>,
+}
+
+/// One forward in a profile, mirroring the `kdbg forward` arguments
+#[derive(Debug, Clone, Deserialize)]
+pub struct ForwardSpec {
+    pub target: String,
+    pub ports: Vec<String>,
+    #[serde(default)]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub auto: bool,
+}
+
+/// Settings for `kdbg debug`
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct DebugConfig {
+    /// Named debug pod profiles, used with `kdbg debug --profile <name>`.
+    /// These are checked before the built-in ones.
+    pub profiles: BTreeMap<String, DebugProfile>,
+    /// How long a debug pod may live before `kdbg debug gc` removes it
+    pub ttl: String,
+    /// Sweep expired debug pods in the background when kdbg starts
+    pub auto_gc: bool,
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            profiles: BTreeMap::new(),
+            ttl: "1h".to_string(),
+            auto_gc: false,
+        }
+    }
+}
+
+/// Settings for `kdbg watch`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct WatchConfig {
+    /// Rules checked against every pod while `kdbg watch` runs
+    pub alerts: Vec<AlertRule>,
+}
+
+/// Pod states an alert rule can match
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// A container is in CrashLoopBackOff
+    CrashLoop,
+    /// Restarts (all containers) above `threshold`
+    Restarts,
+    /// Pod Pending for longer than `for`
+    Pending,
+    /// A container was OOMKilled
+    OomKilled,
+}
+
+/// An alert: when a pod matches `when` for at least `for`, run `command`
+/// and/or POST to `webhook`, then stay quiet for that pod for `cooldown`
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertRule {
+    /// Shown in the watch log and passed to hooks; defaults to the condition
+    #[serde(default)]
+    pub name: Option<String>,
+    pub when: AlertCondition,
+    /// Restart count to exceed, for `restarts`
+    #[serde(default)]
+    pub threshold: Option<u64>,
+    /// How long the condition must hold before firing (e.g. "30s", "5m")
+    #[serde(default, rename = "for")]
+    pub for_duration: Option<String>,
+    /// Minimum time between two alerts for the same pod
+    #[serde(default = "default_cooldown")]
+    pub cooldown: String,
+    /// Only pods in this namespace
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Only pods whose name contains this
+    #[serde(default)]
+    pub pod: Option<String>,
+    /// Run through `sh -c` with KDBG_* variables describing the pod
+    #[serde(default)]
+    pub command: Option<String>,
+    /// URL to POST a JSON description of the alert to
+    #[serde(default)]
+    pub webhook: Option<String>,
+}
+
+fn default_cooldown() -> String {
+    "5m".to_string()
+}
+
+/// Everything that goes into a generated debug pod. Kubernetes-shaped
+/// fields (tolerations, securityContext, resources) are passed through as-is.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DebugProfile {
+    pub image: Option<String>,
+    /// Container command; defaults to keeping the pod alive for `exec`
+    pub command: Vec<String>,
+    pub node_selector: BTreeMap<String, String>,
+    pub tolerations: Vec<Value>,
+    pub service_account: Option<String>,
+    /// Container securityContext
+    pub security_context: Option<Value>,
+    /// Container resources (requests/limits)
+    pub resources: Option<Value>,
+    pub env: BTreeMap<String, String>,
+}
+
+impl DebugConfig {
+    /// Look up a profile, falling back to the built-in ones
+    pub fn profile(&self, name: &str) -> Option<DebugProfile> {
+        self.profiles
+            .get(name)
+            .cloned()
+            .or_else(|| builtin_debug_profiles().remove(name))
+    }
+
+    /// Names of all configured and built-in profiles
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .profiles
+            .keys()
+            .cloned()
+            .chain(builtin_debug_profiles().into_keys())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+fn builtin_debug_profiles() -> BTreeMap<String, DebugProfile> {
+    let mut profiles = BTreeMap::new();
+    profiles.insert(
+        "netshoot".to_string(),
+        DebugProfile {
+            image: Some("nicolaka/netshoot".to_string()),
+            security_context: Some(serde_json::json!({
+                "capabilities": { "add": ["NET_ADMIN", "NET_RAW"] }
+            })),
+            ..Default::default()
+        },
+    );
+    profiles.insert(
+        "alpine-tools".to_string(),
+        DebugProfile {
+            image: Some("alpine:3".to_string()),
+            command: vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "apk add --no-cache curl bind-tools jq procps && sleep infinity".to_string(),
+            ],
+            ..Default::default()
+        },
+    );
+    profiles
+}
+
+impl Config {
+    /// Load the config file, falling back to defaults when it doesn't exist
+    pub fn load() -> Result<Self> {
+        let path = get_kdbg_dir().join("config.json");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)?;
+        serde_json::from_str(&contents).with_context(|| format!("Invalid {}", path.display()))
+    }
+}
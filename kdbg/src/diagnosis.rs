@@ -0,0 +1,622 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Critical,
+    Warning,
+    Info,
+}
+
+/// One problem spotted by a rule, with a suggested next step
+#[derive(Debug)]
+pub struct Finding {
+    pub severity: Severity,
+    pub title: String,
+    pub details: Vec<String>,
+    pub suggestion: String,
+}
+
+/// Everything the rules look at. Kept free of kubectl calls so rules can be
+/// run against fixture JSON.
+pub struct PodContext<'a> {
+    pub pod: &'a Value,
+    pub events: &'a [Value],
+    /// Previous-instance logs keyed by container name
+    pub previous_logs: &'a HashMap<String, String>,
+    pub now: DateTime<Utc>,
+}
+
+impl PodContext<'_> {
+    fn name(&self) -> &str {
+        self.pod["metadata"]["name"].as_str().unwrap_or("unknown")
+    }
+
+    fn namespace(&self) -> &str {
+        self.pod["metadata"]["namespace"]
+            .as_str()
+            .unwrap_or("default")
+    }
+
+    fn container_statuses(&self) -> Vec<&Value> {
+        ["initContainerStatuses", "containerStatuses"]
+            .iter()
+            .filter_map(|key| self.pod["status"][key].as_array())
+            .flatten()
+            .collect()
+    }
+
+    fn container_spec(&self, name: &str) -> &Value {
+        ["initContainers", "containers"]
+            .iter()
+            .filter_map(|key| self.pod["spec"][key].as_array())
+            .flatten()
+            .find(|c| c["name"].as_str() == Some(name))
+            .unwrap_or(&Value::Null)
+    }
+
+    fn events_with_reason(&self, reasons: &[&str]) -> Vec<&Value> {
+        self.events
+            .iter()
+            .filter(|e| reasons.contains(&e["reason"].as_str().unwrap_or("")))
+            .collect()
+    }
+}
+
+type Rule = fn(&PodContext) -> Vec<Finding>;
+
+/// All rules, in the order their findings are reported
+pub const RULES: &[(&str, Rule)] = &[
+    ("image-pull", image_pull),
+    ("oom-killed", oom_killed),
+    ("crash-loop", crash_loop),
+    ("failing-probes", failing_probes),
+    ("unschedulable", unschedulable),
+    ("stuck-terminating", stuck_terminating),
+];
+
+/// Run every rule and return findings, most severe first
+pub fn diagnose(ctx: &PodContext) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = RULES.iter().flat_map(|(_, rule)| rule(ctx)).collect();
+    findings.sort_by_key(|f| f.severity);
+    findings
+}
+
+/// Containers which have restarted and so have previous logs worth fetching
+pub fn containers_needing_logs(pod: &Value) -> Vec<String> {
+    ["initContainerStatuses", "containerStatuses"]
+        .iter()
+        .filter_map(|key| pod["status"][key].as_array())
+        .flatten()
+        .filter(|c| c["restartCount"].as_u64().unwrap_or(0) > 0)
+        .filter_map(|c| c["name"].as_str().map(str::to_string))
+        .collect()
+}
+
+fn image_pull(ctx: &PodContext) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for status in ctx.container_statuses() {
+        let reason = status["state"]["waiting"]["reason"].as_str().unwrap_or("");
+        if !matches!(
+            reason,
+            "ImagePullBackOff" | "ErrImagePull" | "InvalidImageName"
+        ) {
+            continue;
+        }
+
+        let container = status["name"].as_str().unwrap_or("unknown");
+        let image = ctx.container_spec(container)["image"]
+            .as_str()
+            .unwrap_or("unknown");
+
+        let pull_errors: Vec<&str> = ctx
+            .events_with_reason(&["Failed"])
+            .iter()
+            .filter_map(|e| e["message"].as_str())
+            .filter(|m| m.contains(image) || m.contains("pull"))
+            .collect();
+        let message = pull_errors
+            .last()
+            .copied()
+            .or_else(|| status["state"]["waiting"]["message"].as_str())
+            .unwrap_or("");
+        let lower = message.to_lowercase();
+
+        let auth = [
+            "unauthorized",
+            "authentication required",
+            "denied",
+            "401",
+            "403",
+        ]
+        .iter()
+        .any(|s| lower.contains(s));
+        let missing = [
+            "not found",
+            "manifest unknown",
+            "notfound",
+            "does not exist",
+        ]
+        .iter()
+        .any(|s| lower.contains(s));
+
+        let has_pull_secrets = ctx.pod["spec"]["imagePullSecrets"]
+            .as_array()
+            .is_some_and(|s| !s.is_empty());
+
+        let mut details = vec![format!("Image: {}", image)];
+        if !message.is_empty() {
+            details.push(format!("Error: {}", message));
+        }
+
+        let (title, suggestion) = if reason == "InvalidImageName" {
+            (
+                format!("Container '{}' has an invalid image name", container),
+                format!(
+                    "kubectl get pod {} -n {} -o jsonpath='{{.spec.containers[*].image}}'",
+                    ctx.name(),
+                    ctx.namespace()
+                ),
+            )
+        } else if auth {
+            details.push(if has_pull_secrets {
+                "imagePullSecrets are set but were rejected by the registry".to_string()
+            } else {
+                "The pod has no imagePullSecrets".to_string()
+            });
+            (
+                format!(
+                    "Container '{}' can't pull its image: registry authentication failed",
+                    container
+                ),
+                format!(
+                    "kubectl get pod {} -n {} -o jsonpath='{{.spec.imagePullSecrets}}' && kubectl get secrets -n {}",
+                    ctx.name(),
+                    ctx.namespace(),
+                    ctx.namespace()
+                ),
+            )
+        } else if missing {
+            (
+                format!(
+                    "Container '{}' can't pull its image: the image or tag doesn't exist",
+                    container
+                ),
+                format!(
+                    "Check the tag exists in the registry: docker manifest inspect {}",
+                    image
+                ),
+            )
+        } else {
+            (
+                format!(
+                    "Container '{}' can't pull its image ({})",
+                    container, reason
+                ),
+                format!("kdbg events {} -n {}", ctx.name(), ctx.namespace()),
+            )
+        };
+
+        findings.push(Finding {
+            severity: Severity::Critical,
+            title,
+            details,
+            suggestion,
+        });
+    }
+
+    findings
+}
+
+fn oom_killed(ctx: &PodContext) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for status in ctx.container_statuses() {
+        let oom = ["state", "lastState"]
+            .iter()
+            .any(|key| status[key]["terminated"]["reason"].as_str() == Some("OOMKilled"));
+        if !oom {
+            continue;
+        }
+
+        let container = status["name"].as_str().unwrap_or("unknown");
+        let resources = &ctx.container_spec(container)["resources"];
+        let limit = resources["limits"]["memory"].as_str();
+        let request = resources["requests"]["memory"].as_str();
+
+        let mut details = vec![format!(
+            "Restarts: {}",
+            status["restartCount"].as_u64().unwrap_or(0)
+        )];
+        let suggestion = match limit {
+            Some(limit) => {
+                details.push(format!(
+                    "Memory limit: {} (request: {})",
+                    limit,
+                    request.unwrap_or("none")
+                ));
+                details.push(
+                    "The container hit its memory limit; raise the limit or fix the leak"
+                        .to_string(),
+                );
+                format!(
+                    "kubectl top pod {} -n {} --containers",
+                    ctx.name(),
+                    ctx.namespace()
+                )
+            }
+            None => {
+                details.push(
+                    "No memory limit is set, so the node ran out of memory and the kernel picked this container"
+                        .to_string(),
+                );
+                format!(
+                    "kubectl describe node {}",
+                    ctx.pod["spec"]["nodeName"].as_str().unwrap_or("<node>")
+                )
+            }
+        };
+
+        findings.push(Finding {
+            severity: Severity::Critical,
+            title: format!("Container '{}' was OOMKilled", container),
+            details,
+            suggestion,
+        });
+    }
+
+    findings
+}
+
+fn crash_loop(ctx: &PodContext) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for status in ctx.container_statuses() {
+        if status["state"]["waiting"]["reason"].as_str() != Some("CrashLoopBackOff") {
+            continue;
+        }
+
+        let container = status["name"].as_str().unwrap_or("unknown");
+        let last = &status["lastState"]["terminated"];
+        let mut details = vec![format!(
+            "Restarts: {}, last exit code: {} ({})",
+            status["restartCount"].as_u64().unwrap_or(0),
+            last["exitCode"]
+                .as_i64()
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| "?".to_string()),
+            last["reason"].as_str().unwrap_or("unknown")
+        )];
+
+        match ctx.previous_logs.get(container) {
+            Some(logs) if !logs.trim().is_empty() => {
+                details.push("Last log lines before the crash:".to_string());
+                let lines: Vec<&str> = logs.lines().collect();
+                for line in &lines[lines.len().saturating_sub(10)..] {
+                    details.push(format!("  | {}", line));
+                }
+            }
+            _ => details.push("The previous instance left no logs".to_string()),
+        }
+
+        findings.push(Finding {
+            severity: Severity::Critical,
+            title: format!("Container '{}' is in CrashLoopBackOff", container),
+            details,
+            suggestion: format!(
+                "kubectl logs {} -n {} -c {} --previous",
+                ctx.name(),
+                ctx.namespace(),
+                container
+            ),
+        });
+    }
+
+    findings
+}
+
+fn failing_probes(ctx: &PodContext) -> Vec<Finding> {
+    let mut counts: Vec<(&str, u64, &str)> = Vec::new();
+
+    for event in ctx.events_with_reason(&["Unhealthy"]) {
+        let message = event["message"].as_str().unwrap_or("");
+        let probe = ["Liveness", "Readiness", "Startup"]
+            .into_iter()
+            .find(|p| message.starts_with(p))
+            .unwrap_or("Unknown");
+        let count = event["count"].as_u64().unwrap_or(1);
+
+        match counts.iter_mut().find(|(p, _, _)| *p == probe) {
+            Some(entry) => {
+                entry.1 += count;
+                entry.2 = message;
+            }
+            None => counts.push((probe, count, message)),
+        }
+    }
+
+    counts
+        .into_iter()
+        .map(|(probe, count, message)| Finding {
+            // Liveness failures restart the container, readiness only pulls
+            // it from the Service
+            severity: if probe == "Readiness" {
+                Severity::Warning
+            } else {
+                Severity::Critical
+            },
+            title: format!("{} probe failing ({} times)", probe, count),
+            details: vec![format!("Latest: {}", message.trim())],
+            suggestion: format!(
+                "kdbg describe {} -n {} --summary",
+                ctx.name(),
+                ctx.namespace()
+            ),
+        })
+        .collect()
+}
+
+fn unschedulable(ctx: &PodContext) -> Vec<Finding> {
+    if ctx.pod["status"]["phase"].as_str() != Some("Pending") {
+        return Vec::new();
+    }
+
+    let condition_message = ctx.pod["status"]["conditions"]
+        .as_array()
+        .and_then(|conds| {
+            conds.iter().find(|c| {
+                c["type"].as_str() == Some("PodScheduled") && c["status"].as_str() == Some("False")
+            })
+        })
+        .and_then(|c| c["message"].as_str());
+    let event_message = ctx
+        .events_with_reason(&["FailedScheduling"])
+        .last()
+        .and_then(|e| e["message"].as_str());
+
+    let Some(message) = event_message.or(condition_message) else {
+        return Vec::new();
+    };
+    let lower = message.to_lowercase();
+
+    let mut causes = Vec::new();
+    if lower.contains("insufficient") {
+        causes.push((
+            "Not enough allocatable CPU/memory on any node for the pod's requests",
+            "kubectl describe nodes | grep -A 8 'Allocated resources'".to_string(),
+        ));
+    }
+    if lower.contains("taint") {
+        causes.push((
+            "Nodes have taints the pod doesn't tolerate",
+            "kubectl get nodes -o custom-columns=NAME:.metadata.name,TAINTS:.spec.taints"
+                .to_string(),
+        ));
+    }
+    if lower.contains("persistentvolumeclaim") || lower.contains("volume node affinity") {
+        causes.push((
+            "A PersistentVolumeClaim is unbound or its volume is on an unreachable node",
+            format!("kubectl get pvc -n {}", ctx.namespace()),
+        ));
+    }
+    // "volume node affinity conflict" is about where the volume lives (the
+    // cause above), not the pod's own affinity
+    let pod_constraints = lower.replace("volume node affinity", "");
+    if pod_constraints.contains("node affinity") || pod_constraints.contains("node selector") {
+        causes.push((
+            "No node matches the pod's nodeSelector/affinity",
+            format!(
+                "kubectl get pod {} -n {} -o jsonpath='{{.spec.nodeSelector}}{{.spec.affinity}}'",
+                ctx.name(),
+                ctx.namespace()
+            ),
+        ));
+    }
+
+    if causes.is_empty() {
+        causes.push((
+            "The scheduler couldn't place the pod",
+            format!("kdbg events {} -n {}", ctx.name(), ctx.namespace()),
+        ));
+    }
+
+    causes
+        .into_iter()
+        .map(|(cause, suggestion)| Finding {
+            severity: Severity::Critical,
+            title: format!("Pod is Pending: {}", cause),
+            details: vec![format!("Scheduler: {}", message.trim())],
+            suggestion,
+        })
+        .collect()
+}
+
+fn stuck_terminating(ctx: &PodContext) -> Vec<Finding> {
+    let Some(deleted_at) = ctx.pod["metadata"]["deletionTimestamp"]
+        .as_str()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+    else {
+        return Vec::new();
+    };
+
+    // deletionTimestamp already includes the grace period, so anything well
+    // past it is stuck
+    let overdue = ctx
+        .now
+        .signed_duration_since(deleted_at.with_timezone(&Utc))
+        .num_seconds();
+    if overdue < 60 {
+        return Vec::new();
+    }
+
+    let finalizers: Vec<&str> = ctx.pod["metadata"]["finalizers"]
+        .as_array()
+        .map(|f| f.iter().filter_map(|s| s.as_str()).collect())
+        .unwrap_or_default();
+
+    let mut details = vec![format!("Deletion is {}s overdue", overdue)];
+    let suggestion = if finalizers.is_empty() {
+        details.push("No finalizers; the kubelet on the node may be unreachable".to_string());
+        format!(
+            "kubectl delete pod {} -n {} --grace-period=0 --force",
+            ctx.name(),
+            ctx.namespace()
+        )
+    } else {
+        details.push(format!("Finalizers: {}", finalizers.join(", ")));
+        format!(
+            "kubectl patch pod {} -n {} --type=merge -p '{{\"metadata\":{{\"finalizers\":null}}}}'",
+            ctx.name(),
+            ctx.namespace()
+        )
+    };
+
+    vec![Finding {
+        severity: Severity::Warning,
+        title: "Pod is stuck Terminating".to_string(),
+        details,
+        suggestion,
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run every rule against a fixture from tests/fixtures/diagnosis
+    fn diagnose_fixture(name: &str) -> Vec<Finding> {
+        let path = format!(
+            "{}/tests/fixtures/diagnosis/{}.json",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        let fixture: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|e| panic!("{}: {}", path, e));
+
+        let events = fixture["events"].as_array().cloned().unwrap_or_default();
+        let previous_logs: HashMap<String, String> =
+            serde_json::from_value(fixture["previous_logs"].clone()).unwrap_or_default();
+        let now = fixture["now"]
+            .as_str()
+            .map(|t| DateTime::parse_from_rfc3339(t).unwrap().with_timezone(&Utc))
+            .unwrap_or_else(Utc::now);
+
+        diagnose(&PodContext {
+            pod: &fixture["pod"],
+            events: &events,
+            previous_logs: &previous_logs,
+            now,
+        })
+    }
+
+    fn titles(findings: &[Finding]) -> Vec<&str> {
+        findings.iter().map(|f| f.title.as_str()).collect()
+    }
+
+    #[test]
+    fn image_pull_auth_failure() {
+        let findings = diagnose_fixture("image_pull");
+        assert_eq!(
+            titles(&findings),
+            ["Container 'app' can't pull its image: registry authentication failed"]
+        );
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert!(findings[0]
+            .details
+            .contains(&"The pod has no imagePullSecrets".to_string()));
+        assert!(findings[0].suggestion.contains("imagePullSecrets"));
+    }
+
+    #[test]
+    fn oom_killed_with_limit() {
+        let findings = diagnose_fixture("oom_killed");
+        assert_eq!(titles(&findings), ["Container 'worker' was OOMKilled"]);
+        assert!(findings[0]
+            .details
+            .contains(&"Memory limit: 256Mi (request: 128Mi)".to_string()));
+        assert_eq!(
+            findings[0].suggestion,
+            "kubectl top pod worker-5c6b -n batch --containers"
+        );
+    }
+
+    #[test]
+    fn crash_loop_includes_previous_logs() {
+        let findings = diagnose_fixture("crash_loop");
+        assert_eq!(
+            titles(&findings),
+            ["Container 'web' is in CrashLoopBackOff"]
+        );
+        assert_eq!(
+            findings[0].details[0],
+            "Restarts: 7, last exit code: 1 (Error)"
+        );
+        assert!(findings[0]
+            .details
+            .contains(&"  | error: DATABASE_URL is not set".to_string()));
+        assert_eq!(
+            findings[0].suggestion,
+            "kubectl logs web-1 -n default -c web --previous"
+        );
+    }
+
+    #[test]
+    fn failing_probes_counted_per_probe() {
+        let findings = diagnose_fixture("failing_probes");
+        // Liveness restarts the container, so it sorts before readiness
+        assert_eq!(
+            titles(&findings),
+            [
+                "Liveness probe failing (2 times)",
+                "Readiness probe failing (5 times)"
+            ]
+        );
+        assert_eq!(findings[0].severity, Severity::Critical);
+        assert_eq!(findings[1].severity, Severity::Warning);
+        assert_eq!(
+            findings[1].details,
+            ["Latest: Readiness probe failed: HTTP probe failed with statuscode: 500"]
+        );
+    }
+
+    #[test]
+    fn unschedulable_prefers_event_message() {
+        let findings = diagnose_fixture("unschedulable");
+        assert_eq!(
+            titles(&findings),
+            [
+                "Pod is Pending: Not enough allocatable CPU/memory on any node for the pod's requests",
+                "Pod is Pending: No node matches the pod's nodeSelector/affinity"
+            ]
+        );
+        assert!(findings[0].details[0].contains("1 Insufficient cpu"));
+    }
+
+    #[test]
+    fn unschedulable_volume_affinity_is_not_pod_affinity() {
+        let findings = diagnose_fixture("unschedulable_volume");
+        assert_eq!(
+            titles(&findings),
+            ["Pod is Pending: A PersistentVolumeClaim is unbound or its volume is on an unreachable node"]
+        );
+        assert_eq!(findings[0].suggestion, "kubectl get pvc -n data");
+    }
+
+    #[test]
+    fn stuck_terminating_with_finalizers() {
+        let findings = diagnose_fixture("stuck_terminating");
+        assert_eq!(titles(&findings), ["Pod is stuck Terminating"]);
+        assert_eq!(
+            findings[0].details,
+            [
+                "Deletion is 600s overdue",
+                "Finalizers: example.com/cleanup"
+            ]
+        );
+        assert!(findings[0]
+            .suggestion
+            .starts_with("kubectl patch pod old-api -n prod"));
+    }
+}
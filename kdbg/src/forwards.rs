@@ -0,0 +1,202 @@
+use crate::utils::get_kdbg_dir;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::Duration;
+
+/// Set in the environment of background forwards so the forward loop can
+/// report which pod and ports it ended up using
+pub const FORWARD_ID_ENV: &str = "KDBG_FORWARD_ID";
+
+/// A background port-forward, persisted as ~/.kdbg/forwards/<id>.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForwardState {
+    pub id: String,
+    pub pid: u32,
+    /// Start time of `pid`, so a reused pid is never mistaken for the forward
+    #[serde(default)]
+    pub pid_start: Option<String>,
+    pub target: String,
+    pub namespace: Option<String>,
+    /// Port specs as given on the command line
+    pub ports: Vec<String>,
+    pub auto: bool,
+    /// Profile that started this forward, if any
+    pub profile: Option<String>,
+    pub started_at: String,
+    /// Pod currently forwarded to, filled in by the forward loop
+    #[serde(default)]
+    pub pod: Option<String>,
+    /// Resolved LOCAL:REMOTE mappings
+    #[serde(default)]
+    pub mappings: Vec<String>,
+    #[serde(default)]
+    pub reconnects: u32,
+}
+
+/// Get the directory background forward state is kept in (~/.kdbg/forwards)
+pub fn get_forwards_dir() -> PathBuf {
+    get_kdbg_dir().join("forwards")
+}
+
+impl ForwardState {
+    pub fn path(&self) -> PathBuf {
+        get_forwards_dir().join(format!("{}.json", self.id))
+    }
+
+    pub fn log_path(&self) -> PathBuf {
+        get_forwards_dir().join(format!("{}.log", self.id))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all(get_forwards_dir())?;
+        fs::write(self.path(), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(id: &str) -> Result<Self> {
+        let path = get_forwards_dir().join(format!("{}.json", id));
+        let contents =
+            fs::read_to_string(&path).with_context(|| format!("No forward with id '{}'", id))?;
+        serde_json::from_str(&contents).with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    /// All known forwards, ordered by id
+    pub fn load_all() -> Vec<Self> {
+        let mut forwards: Vec<Self> = fs::read_dir(get_forwards_dir())
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+                    .filter_map(|p| fs::read_to_string(p).ok())
+                    .filter_map(|c| serde_json::from_str(&c).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        forwards.sort_by_key(|f: &Self| f.id.parse::<u32>().unwrap_or(u32::MAX));
+        forwards
+    }
+
+    /// Take the lowest free id and save under it. The state file is created
+    /// exclusively, so forwards started at the same time get different ids.
+    pub fn save_new(&mut self) -> Result<()> {
+        fs::create_dir_all(get_forwards_dir())?;
+        for n in 1u32.. {
+            self.id = n.to_string();
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(self.path())
+            {
+                Ok(mut file) => {
+                    file.write_all(serde_json::to_string_pretty(self)?.as_bytes())?;
+                    return Ok(());
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("Failed to create {}", self.path().display()))
+                }
+            }
+        }
+        unreachable!("ran out of forward ids")
+    }
+
+    pub fn remove(&self) {
+        let _ = fs::remove_file(self.path());
+        let _ = fs::remove_file(self.log_path());
+    }
+
+    pub fn is_running(&self) -> bool {
+        // pid 0 means the process hasn't registered yet; kill(0, ..) would
+        // target our own process group. A pid whose start time changed has
+        // been reused by something else (or the machine rebooted).
+        self.pid != 0 && self.pid_start.is_some() && process_start(self.pid) == self.pid_start
+    }
+
+    /// Terminate the forward and the kubectl it started (same process group)
+    pub fn stop(&self) {
+        if self.is_running() {
+            unsafe {
+                libc::kill(-(self.pid as libc::pid_t), libc::SIGTERM);
+            }
+        }
+        self.remove();
+    }
+
+    /// Local ports that currently accept connections, out of all mapped ones
+    pub fn listening_ports(&self) -> (usize, usize) {
+        let locals: Vec<u16> = self
+            .mappings
+            .iter()
+            .filter_map(|m| m.split(':').next()?.parse().ok())
+            .collect();
+        let listening = locals
+            .iter()
+            .filter(|port| {
+                let addr = SocketAddr::from(([127, 0, 0, 1], **port));
+                TcpStream::connect_timeout(&addr, Duration::from_millis(300)).is_ok()
+            })
+            .count();
+        (listening, locals.len())
+    }
+}
+
+/// Called when a background forward starts: record our pid, which is also
+/// the process group the kubectl children run in
+pub fn record_started() {
+    update_current(|state| {
+        state.pid = std::process::id();
+        state.pid_start = process_start(state.pid);
+    });
+}
+
+/// When a process started, as an opaque string to compare, or None if it
+/// doesn't exist
+fn process_start(pid: u32) -> Option<String> {
+    // Linux: field 22 of /proc/<pid>/stat, in clock ticks since boot. The
+    // command name before it is in parentheses and may contain spaces.
+    if let Ok(stat) = fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        let fields = &stat[stat.rfind(')')? + 1..];
+        return fields.split_whitespace().nth(19).map(String::from);
+    }
+    if std::path::Path::new("/proc/self/stat").exists() {
+        return None;
+    }
+
+    // Elsewhere (macOS) ask ps
+    let output = Command::new("ps")
+        .args(["-o", "lstart=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let start = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !start.is_empty()).then_some(start)
+}
+
+/// Called from the forward loop: record the pod and ports of a (re)connect
+/// when running as a background forward
+pub fn record_connection(pod: &str, mappings: &[String], reconnect: bool) {
+    update_current(|state| {
+        state.pod = Some(pod.to_string());
+        state.mappings = mappings.to_vec();
+        if reconnect {
+            state.reconnects += 1;
+        }
+    });
+}
+
+fn update_current(f: impl FnOnce(&mut ForwardState)) {
+    let Ok(id) = std::env::var(FORWARD_ID_ENV) else {
+        return;
+    };
+    if let Ok(mut state) = ForwardState::load(&id) {
+        f(&mut state);
+        let _ = state.save();
+    }
+}
@@ -59,6 +59,59 @@ pub fn find_pod(pod_pattern: &str, namespace: Option<String>) -> Result<(String,
     Ok((name, ns))
 }
 
+/// Find all pods whose name contains the pattern, optionally narrowed by a
+/// label selector
+pub fn find_pods(
+    pod_pattern: &str,
+    namespace: Option<String>,
+    selector: Option<&str>,
+) -> Result<Vec<Value>> {
+    let mut args = vec!["get", "pods"];
+
+    let ns_str;
+    if let Some(ns) = &namespace {
+        ns_str = ns.clone();
+        args.extend(&["-n", &ns_str]);
+    } else {
+        args.push("--all-namespaces");
+    }
+
+    if let Some(selector) = selector {
+        args.extend(&["-l", selector]);
+    }
+
+    args.extend(&["-o", "json"]);
+
+    let output = Command::new("kubectl").args(&args).output()?;
+    let json: Value = serde_json::from_slice(&output.stdout)?;
+
+    let matches: Vec<Value> = json["items"]
+        .as_array()
+        .map(|pods| {
+            pods.iter()
+                .filter(|pod| {
+                    let name = pod["metadata"]["name"].as_str().unwrap_or("");
+                    name.contains(pod_pattern)
+                })
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if matches.is_empty() {
+        match selector {
+            Some(selector) => anyhow::bail!(
+                "No pods found matching '{}' with selector '{}'",
+                pod_pattern,
+                selector
+            ),
+            None => anyhow::bail!("No pods found matching '{}'", pod_pattern),
+        }
+    }
+
+    Ok(matches)
+}
+
 /// Get all pods as JSON
 pub fn get_pods_json(namespace: Option<String>) -> Result<Value> {
     let mut args = vec!["get", "pods"];
@@ -78,6 +131,231 @@ pub fn get_pods_json(namespace: Option<String>) -> Result<Value> {
     Ok(json)
 }
 
+/// Get all nodes as JSON
+pub fn get_nodes_json() -> Result<Value> {
+    let output = Command::new("kubectl")
+        .args(["get", "nodes", "-o", "json"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("Failed to get nodes");
+    }
+
+    let json: Value = serde_json::from_slice(&output.stdout)?;
+    Ok(json)
+}
+
+/// Get the pods scheduled on a node, across all namespaces
+pub fn get_node_pods(node: &str) -> Result<Vec<Value>> {
+    let output = Command::new("kubectl")
+        .args([
+            "get",
+            "pods",
+            "--all-namespaces",
+            "--field-selector",
+            &format!("spec.nodeName={}", node),
+            "-o",
+            "json",
+        ])
+        .output()?;
+
+    let json: Value = serde_json::from_slice(&output.stdout)?;
+    Ok(json["items"].as_array().cloned().unwrap_or_default())
+}
+
+/// Get a single pod as JSON
+pub fn get_pod_json(pod_name: &str, namespace: &str) -> Result<Value> {
+    let output = Command::new("kubectl")
+        .args(["get", "pod", pod_name, "-n", namespace, "-o", "json"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("Failed to get pod '{}'", pod_name);
+    }
+
+    let json: Value = serde_json::from_slice(&output.stdout)?;
+    Ok(json)
+}
+
+/// Find a container in a pod by pattern (exact name first, then fuzzy)
+pub fn find_container(pod_name: &str, namespace: &str, pattern: &str) -> Result<String> {
+    let pod = get_pod_json(pod_name, namespace)?;
+    let (names, matches) = matching_containers(&pod, pattern);
+
+    match matches.len() {
+        1 => Ok(matches[0].to_string()),
+        0 => {
+            println!(
+                "{} Containers in {}: {}",
+                "[INFO]".yellow(),
+                pod_name.cyan(),
+                names.join(", ")
+            );
+            anyhow::bail!("No container found matching '{}'", pattern)
+        }
+        _ => {
+            println!("{} Multiple containers found:", "[INFO]".yellow());
+            for name in &matches {
+                println!("  - {}", name.cyan());
+            }
+            anyhow::bail!("Please be more specific")
+        }
+    }
+}
+
+/// Like `find_container`, for pod JSON already at hand and without printing
+/// anything, so it can run from worker threads
+pub fn match_container(pod: &Value, pattern: &str) -> Result<String> {
+    let (names, matches) = matching_containers(pod, pattern);
+
+    match matches.len() {
+        1 => Ok(matches[0].to_string()),
+        0 => anyhow::bail!(
+            "No container found matching '{}' (containers: {})",
+            pattern,
+            names.join(", ")
+        ),
+        _ => anyhow::bail!(
+            "Multiple containers match '{}': {}",
+            pattern,
+            matches.join(", ")
+        ),
+    }
+}
+
+/// All container names in a pod, and those matching `pattern`: the exact
+/// name alone if there is one, otherwise every name containing it
+fn matching_containers<'a>(pod: &'a Value, pattern: &'a str) -> (Vec<&'a str>, Vec<&'a str>) {
+    let names: Vec<&str> = ["initContainers", "containers", "ephemeralContainers"]
+        .iter()
+        .filter_map(|key| pod["spec"][key].as_array())
+        .flatten()
+        .filter_map(|c| c["name"].as_str())
+        .collect();
+
+    let matches = if names.contains(&pattern) {
+        vec![pattern]
+    } else {
+        names
+            .iter()
+            .copied()
+            .filter(|name| name.contains(pattern))
+            .collect()
+    };
+
+    (names, matches)
+}
+
+/// Get the events for a pod, oldest first
+pub fn get_pod_events(pod_name: &str, namespace: &str) -> Result<Vec<Value>> {
+    get_events(Some(namespace), Some(pod_name))
+}
+
+/// Get events in a namespace (all namespaces if None), optionally only those
+/// about one object, oldest first
+pub fn get_events(namespace: Option<&str>, object: Option<&str>) -> Result<Vec<Value>> {
+    let output = Command::new("kubectl")
+        .args(["get", "events"])
+        .args(events_scope(namespace, object))
+        .args(["-o", "json"])
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("Failed to get events");
+    }
+
+    let json: Value = serde_json::from_slice(&output.stdout)?;
+    let mut events = json["items"].as_array().cloned().unwrap_or_default();
+    events.sort_by(|a, b| event_time(a).cmp(event_time(b)));
+    Ok(events)
+}
+
+/// `kubectl get events` arguments selecting a namespace and object
+pub fn events_scope(namespace: Option<&str>, object: Option<&str>) -> Vec<String> {
+    let mut args = match namespace {
+        Some(ns) => vec!["-n".to_string(), ns.to_string()],
+        None => vec!["--all-namespaces".to_string()],
+    };
+    if let Some(object) = object {
+        args.push("--field-selector".to_string());
+        args.push(format!("involvedObject.name={}", object));
+    }
+    args
+}
+
+/// Most recent timestamp on an event (events.k8s.io and core/v1 fields)
+pub fn event_time(event: &Value) -> &str {
+    ["lastTimestamp", "eventTime", "firstTimestamp"]
+        .iter()
+        .filter_map(|key| event[key].as_str())
+        .next()
+        .or_else(|| event["metadata"]["creationTimestamp"].as_str())
+        .unwrap_or("")
+}
+
+/// Earliest timestamp on an event
+pub fn event_first_time(event: &Value) -> &str {
+    ["firstTimestamp", "eventTime", "lastTimestamp"]
+        .iter()
+        .filter_map(|key| event[key].as_str())
+        .next()
+        .or_else(|| event["metadata"]["creationTimestamp"].as_str())
+        .unwrap_or("")
+}
+
+/// Status the way `kubectl get pods` shows it: the phase, refined by init
+/// progress, container waiting/terminated reasons and deletion
+pub fn pod_status(pod: &Value) -> String {
+    if !pod["metadata"]["deletionTimestamp"].is_null() {
+        return "Terminating".to_string();
+    }
+
+    let status = &pod["status"];
+    let mut result = status["reason"]
+        .as_str()
+        .or_else(|| status["phase"].as_str())
+        .unwrap_or("Unknown")
+        .to_string();
+
+    let init = status["initContainerStatuses"].as_array();
+    let init_total = init.map_or(0, |cs| cs.len());
+    for (i, container) in init.into_iter().flatten().enumerate() {
+        let state = &container["state"];
+        if state["terminated"]["exitCode"].as_i64() == Some(0) {
+            continue;
+        }
+        return if let Some(reason) = state["terminated"]["reason"].as_str() {
+            format!("Init:{}", reason)
+        } else if let Some(reason) = state["waiting"]["reason"]
+            .as_str()
+            .filter(|r| *r != "PodInitializing")
+        {
+            format!("Init:{}", reason)
+        } else {
+            format!("Init:{}/{}", i, init_total)
+        };
+    }
+
+    for container in status["containerStatuses"].as_array().into_iter().flatten() {
+        let state = &container["state"];
+        if let Some(reason) = state["waiting"]["reason"].as_str() {
+            result = reason.to_string();
+        } else if let Some(reason) = state["terminated"]["reason"].as_str() {
+            result = reason.to_string();
+        }
+    }
+
+    result
+}
+
+/// Restarts summed over all containers
+pub fn restart_count(pod: &Value) -> u64 {
+    pod["status"]["containerStatuses"]
+        .as_array()
+        .map(|cs| cs.iter().filter_map(|c| c["restartCount"].as_u64()).sum())
+        .unwrap_or(0)
+}
+
 /// Execute kubectl command and return output
 pub fn kubectl_exec(args: &[&str]) -> Result<std::process::Output> {
     Ok(Command::new("kubectl").args(args).output()?)
@@ -1,6 +1,13 @@
 // Library exports for kdbg
+pub mod alerts;
 pub mod commands;
+pub mod config;
+pub mod diagnosis;
+pub mod forwards;
 pub mod kubectl;
+pub mod quantity;
+pub mod recorder;
+pub mod summary;
 pub mod utils;
 
 pub use commands::*;
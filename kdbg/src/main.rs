@@ -1,8 +1,11 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
 
 // Import all commands from library
 use kdbg::commands::*;
+use kdbg::config::ForwardSpec;
 
 #[derive(Parser)]
 #[command(name = "kdbg")]
@@ -31,6 +34,15 @@ enum Commands {
         follow: bool,
         #[arg(long, default_value = "100")]
         tail: u32,
+        /// Save logs (current and previous instances) to this directory
+        #[arg(long, conflicts_with = "follow")]
+        save: Option<PathBuf>,
+        /// Package the saved logs as <dir>.tar.gz
+        #[arg(long, requires = "save")]
+        archive: bool,
+        /// Group lines into templates and show counts instead of raw logs
+        #[arg(long, conflicts_with = "save")]
+        summarize: bool,
     },
 
     /// Get logs from multiple pods matching pattern
@@ -42,15 +54,64 @@ enum Commands {
         follow: bool,
         #[arg(long, default_value = "100")]
         tail: u32,
+        /// Save logs (current and previous instances) to this directory
+        #[arg(long, conflicts_with = "follow")]
+        save: Option<PathBuf>,
+        /// Package the saved logs as <dir>.tar.gz
+        #[arg(long, requires = "save")]
+        archive: bool,
+        /// Group lines into templates and show counts instead of raw logs
+        #[arg(long, conflicts_with = "save")]
+        summarize: bool,
     },
 
-    /// Execute command in pod
+    /// Execute command in pod (kdbg exec <pod> -- <cmd> [args...])
     Exec {
-        pod: String,
+        #[arg(required_unless_present = "selector")]
+        pod: Option<String>,
+        #[arg(short, long)]
+        namespace: Option<String>,
+        /// Run in every pod matching the pattern instead of exactly one
+        #[arg(long)]
+        all: bool,
+        /// Label selector for --all
+        #[arg(short = 'l', long, requires = "all")]
+        selector: Option<String>,
+        /// Print each pod's output as a block instead of interleaving lines
+        #[arg(long, requires = "all")]
+        group: bool,
+        /// Maximum number of pods to run in at once with --all
+        #[arg(long, default_value = "10", requires = "all")]
+        parallel: usize,
+        /// Per-pod timeout in seconds with --all
+        #[arg(long, default_value = "30", requires = "all")]
+        timeout: u64,
+        /// Container to exec into (fuzzy matched)
+        #[arg(long)]
+        container: Option<String>,
+        /// Command string; run through `sh -c` when it contains spaces
+        #[arg(short, long, conflicts_with = "args")]
+        command: Option<String>,
+        /// Command and arguments after `--`
+        #[arg(last = true)]
+        args: Vec<String>,
+        /// Record the session to ~/.kdbg/sessions (asciicast v2)
+        #[arg(long, conflicts_with = "all")]
+        record: bool,
+    },
+
+    /// Copy files to or from a pod (kdbg cp <pod>:<path> <local> or the reverse)
+    Cp {
+        src: String,
+        dest: String,
         #[arg(short, long)]
         namespace: Option<String>,
-        #[arg(short, long, default_value = "/bin/sh")]
-        command: String,
+        /// Container to copy from/to (fuzzy matched)
+        #[arg(long)]
+        container: Option<String>,
+        /// Stream a single file through cat for containers without tar
+        #[arg(long)]
+        cat: bool,
     },
 
     /// Describe pod
@@ -58,21 +119,59 @@ enum Commands {
         pod: String,
         #[arg(short, long)]
         namespace: Option<String>,
+        /// Compact digest built from the pod JSON instead of kubectl describe
+        #[arg(short, long)]
+        summary: bool,
+        /// Number of warning events to show with --summary
+        #[arg(long, default_value = "10", requires = "summary")]
+        events: usize,
+    },
+
+    /// Check an unhealthy pod for known problems and suggest next steps
+    Diagnose {
+        pod: String,
+        #[arg(short, long)]
+        namespace: Option<String>,
     },
 
-    /// Show pod resource usage
+    /// Show nodes with capacity, pressure and taints, or the pods on one node
+    Nodes {
+        /// Node name or pattern
+        node: Option<String>,
+    },
+
+    /// Show pod (or node) resource usage against requests and limits
     Top {
+        /// What to show usage for
+        #[arg(value_parser = ["pods", "nodes"], default_value = "pods")]
+        resource: String,
+        /// Namespace of the pods to show
         #[arg(short, long)]
         namespace: Option<String>,
+        /// Sort pods by usage or by limit utilization
+        #[arg(long, value_parser = ["cpu", "memory", "cpu-limit%", "memory-limit%"])]
+        sort_by: Option<String>,
+        /// Show a per-container breakdown under each pod
+        #[arg(long)]
+        containers: bool,
     },
 
     /// Port forward to pod
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Forward {
-        pod: String,
-        local_port: u16,
-        pod_port: u16,
+        #[command(subcommand)]
+        action: Option<ForwardAction>,
+        /// Pod pattern, svc/<name> or deploy/<name>
+        #[arg(required = true)]
+        target: Option<String>,
+        /// Port mappings: LOCAL:REMOTE, PORT or :REMOTE (REMOTE may be a named port)
+        #[arg(required = true)]
+        ports: Vec<String>,
         #[arg(short, long)]
         namespace: Option<String>,
+        /// Pick free local ports for mappings without an explicit local port
+        #[arg(long)]
+        auto: bool,
     },
 
     /// Open interactive shell in pod
@@ -80,40 +179,161 @@ enum Commands {
         pod: String,
         #[arg(short, long)]
         namespace: Option<String>,
+        /// Container to open the shell in (fuzzy matched)
+        #[arg(long)]
+        container: Option<String>,
+        /// Shell to try (repeatable); defaults to the configured list
+        #[arg(long = "shell", value_name = "SHELL")]
+        shells: Vec<String>,
+        /// Inject helper aliases and a PS1 showing pod/namespace
+        #[arg(long)]
+        rc: bool,
+        /// Record the session to ~/.kdbg/sessions (asciicast v2)
+        #[arg(long)]
+        record: bool,
     },
 
     /// Create debug pod and shell into it
+    #[command(args_conflicts_with_subcommands = true)]
     Debug {
-        #[arg(short, long, default_value = "busybox")]
-        image: String,
-        #[arg(short, long, default_value = "default")]
-        namespace: String,
+        #[command(subcommand)]
+        action: Option<DebugAction>,
+        /// Pod to debug (omit for a standalone debug pod)
+        pod: Option<String>,
+        /// Debug image [default: busybox; with --copy: keep the original]
+        #[arg(short, long)]
+        image: Option<String>,
+        #[arg(short, long)]
+        namespace: Option<String>,
+        /// Container to target: shares its processes, or is the one changed with --copy
+        #[arg(long, requires = "pod")]
+        target: Option<String>,
+        /// Debug a copy of the pod with a changed image or command
+        #[arg(long, requires = "pod")]
+        copy: bool,
+        /// Run the debug pod on this node; without --profile, a privileged
+        /// pod with the host's namespaces and / mounted at /host
+        #[arg(long, conflicts_with_all = ["pod", "copy", "target"])]
+        node: Option<String>,
+        /// Debug pod profile from ~/.kdbg/config.json (built-in: netshoot, alpine-tools)
+        #[arg(short, long)]
+        profile: Option<String>,
+        /// Print the generated pod manifest instead of creating it
+        #[arg(long, conflicts_with = "pod")]
+        dry_run: bool,
+        /// Record the session to ~/.kdbg/sessions (asciicast v2)
+        #[arg(long)]
+        record: bool,
+        /// Command for the copied container (with --copy), e.g. -- sleep infinity
+        #[arg(last = true, requires = "copy")]
+        command: Vec<String>,
     },
 
-    /// Restart pod (delete and let it recreate)
+    /// Restart pod (delete it and wait for its controller's replacement)
     Restart {
         pod: String,
         #[arg(short, long)]
         namespace: Option<String>,
+        /// Restart every pod matching the pattern, waiting for each batch
+        #[arg(long)]
+        all: bool,
+        /// Label selector for --all
+        #[arg(short = 'l', long, requires = "all")]
+        selector: Option<String>,
+        /// Number of pods to restart at once with --all
+        #[arg(long, default_value = "1", requires = "all")]
+        parallel: usize,
+        /// Rollout restart the owning Deployment/StatefulSet/DaemonSet instead
+        #[arg(long, conflicts_with = "all")]
+        rollout: bool,
+        /// Show what would happen without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Don't ask for confirmation (with --all, also delete pods without a controller)
+        #[arg(short, long)]
+        yes: bool,
+        /// Seconds to wait for the replacement to become Ready
+        #[arg(long, default_value = "120")]
+        timeout: u64,
     },
 
-    /// Show pod events
+    /// Show events for a pod, a namespace, or the whole cluster
     Events {
-        pod: String,
+        /// Pod to show events for (omit for all events in the namespace/cluster)
+        pod: Option<String>,
+        /// Namespace (all namespaces if omitted)
+        #[arg(short, long)]
+        namespace: Option<String>,
+        /// Only events of this type (Normal, Warning)
+        #[arg(long = "type")]
+        event_type: Option<String>,
+        /// Only events about objects of this kind (Pod, Node, Deployment, ...)
+        #[arg(long)]
+        kind: Option<String>,
+        /// Only events whose reason contains this (e.g. BackOff)
+        #[arg(long)]
+        reason: Option<String>,
+        /// Only events seen within this window (e.g. 10m, 2h)
+        #[arg(long)]
+        since: Option<String>,
+        /// Group repeated events by object and reason
+        #[arg(short, long)]
+        aggregate: bool,
+        /// Keep streaming new events
+        #[arg(short, long, conflicts_with = "aggregate")]
+        watch: bool,
+    },
+
+    /// Chronological view of pod lifecycle, container states, events and logs
+    Timeline {
+        /// Pod name pattern, or a label selector such as app=api
+        target: String,
         #[arg(short, long)]
         namespace: Option<String>,
+        /// Only entries within this window (e.g. 30m, 2h)
+        #[arg(long)]
+        since: Option<String>,
+        /// Include log lines containing this text
+        #[arg(short, long)]
+        grep: Option<String>,
+        /// Print entries as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Watch pods in real-time
     Watch {
         #[arg(short, long)]
         namespace: Option<String>,
-        #[arg(short, long, default_value = "2")]
-        interval: u64,
+        /// No longer used: watch redraws as soon as pods change
+        #[arg(short, long, hide = true)]
+        interval: Option<u64>,
     },
 
     /// Switch kubectl context
-    Ctx { context: Option<String> },
+    Ctx {
+        /// Context to switch to (fuzzy matched, "-" for the previous one)
+        context: Option<String>,
+        /// Open a subshell using only this context, leaving the global
+        /// kubeconfig (and other terminals) untouched
+        #[arg(long, requires = "context")]
+        shell: bool,
+        /// With --shell: print an `export` line for eval instead
+        #[arg(long, requires = "shell")]
+        export: bool,
+    },
+
+    /// Set the namespace of the current context
+    Ns {
+        /// Namespace to switch to (fuzzy matched)
+        namespace: Option<String>,
+    },
+
+    /// List and replay recorded sessions
+    Sessions {
+        #[command(subcommand)]
+        action: SessionsAction,
+    },
 
     /// Run a plugin command
     Plugin {
@@ -123,9 +343,78 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum ForwardAction {
+    /// Start a forward in the background
+    Start {
+        /// Pod pattern, svc/<name> or deploy/<name>
+        target: String,
+        /// Port mappings: LOCAL:REMOTE, PORT or :REMOTE
+        #[arg(required = true)]
+        ports: Vec<String>,
+        #[arg(short, long)]
+        namespace: Option<String>,
+        /// Pick free local ports for mappings without an explicit local port
+        #[arg(long)]
+        auto: bool,
+    },
+
+    /// List background forwards and their health
+    List,
+
+    /// Stop a background forward by id, profile name, or "all"
+    Stop { which: String },
+
+    /// Start every forward of a profile from ~/.kdbg/config.json
+    Up { profile: String },
+}
+
+#[derive(Subcommand)]
+enum DebugAction {
+    /// List kdbg-created pods and delete the expired ones
+    Gc {
+        /// Delete pods older than this (e.g. 30m, 2h) instead of using their TTL
+        #[arg(long)]
+        older_than: Option<String>,
+        /// Only list what would be deleted
+        #[arg(long)]
+        dry_run: bool,
+        /// No output (used for the automatic background sweep)
+        #[arg(long, hide = true)]
+        quiet: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionsAction {
+    /// List recorded sessions
+    List,
+
+    /// Replay a recorded session in the terminal
+    Replay {
+        id: String,
+        /// Playback speed multiplier
+        #[arg(long, default_value = "1.0", value_parser = positive_number)]
+        speed: f64,
+        /// Cap pauses between events at this many seconds
+        #[arg(long, default_value = "2.0", value_parser = non_negative_number)]
+        max_idle: f64,
+    },
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    if !matches!(
+        cli.command,
+        Commands::Debug {
+            action: Some(DebugAction::Gc { .. }),
+            ..
+        }
+    ) {
+        auto_gc();
+    }
+
     match cli.command {
         Commands::List { namespace, verbose } => list_pods(namespace, verbose)?,
         Commands::Logs {
@@ -133,37 +422,260 @@ fn main() -> Result<()> {
             namespace,
             follow,
             tail,
-        } => show_logs(&pod, namespace, follow, tail)?,
+            save,
+            archive,
+            summarize,
+        } => show_logs(
+            &pod,
+            namespace,
+            follow,
+            tail,
+            save.as_deref(),
+            archive,
+            summarize,
+        )?,
         Commands::MultiLogs {
             pod,
             namespace,
             follow,
             tail,
-        } => multi_logs(&pod, namespace, follow, tail)?,
+            save,
+            archive,
+            summarize,
+        } => multi_logs(
+            &pod,
+            namespace,
+            follow,
+            tail,
+            save.as_deref(),
+            archive,
+            summarize,
+        )?,
         Commands::Exec {
             pod,
             namespace,
+            all,
+            selector,
+            group,
+            parallel,
+            timeout,
+            container,
             command,
-        } => exec_pod(&pod, namespace, &command)?,
-        Commands::Describe { pod, namespace } => describe_pod(&pod, namespace)?,
-        Commands::Top { namespace } => show_top(namespace)?,
+            args,
+            record,
+        } => {
+            let pod = pod.unwrap_or_default();
+            let code = if all {
+                exec_all(
+                    &pod,
+                    namespace,
+                    selector.as_deref(),
+                    container.as_deref(),
+                    command_argv(command.as_deref(), &args),
+                    FanOut {
+                        grouped: group,
+                        parallel,
+                        timeout: Duration::from_secs(timeout),
+                    },
+                )?
+            } else {
+                exec_pod(
+                    &pod,
+                    namespace,
+                    container.as_deref(),
+                    command.as_deref(),
+                    &args,
+                    record,
+                )?
+            };
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+        Commands::Cp {
+            src,
+            dest,
+            namespace,
+            container,
+            cat,
+        } => copy_files(&src, &dest, namespace, container.as_deref(), cat)?,
+        Commands::Describe {
+            pod,
+            namespace,
+            summary,
+            events,
+        } => describe_pod(&pod, namespace, summary, events)?,
+        Commands::Diagnose { pod, namespace } => diagnose_pod(&pod, namespace)?,
+        Commands::Top {
+            resource,
+            namespace,
+            sort_by,
+            containers,
+        } => {
+            if resource == "nodes" {
+                if namespace.is_some() || sort_by.is_some() || containers {
+                    anyhow::bail!("-n, --sort-by and --containers only apply to 'kdbg top pods'");
+                }
+                show_top_nodes()?
+            } else {
+                show_top(namespace, sort_by.as_deref(), containers)?
+            }
+        }
+        Commands::Nodes { node } => show_nodes(node.as_deref())?,
         Commands::Forward {
+            action,
+            target,
+            ports,
+            namespace,
+            auto,
+        } => match action {
+            Some(ForwardAction::Start {
+                target,
+                ports,
+                namespace,
+                auto,
+            }) => start_forward(
+                &ForwardSpec {
+                    target,
+                    ports,
+                    namespace,
+                    auto,
+                },
+                None,
+            )?,
+            Some(ForwardAction::List) => list_forwards()?,
+            Some(ForwardAction::Stop { which }) => stop_forwards(&which)?,
+            Some(ForwardAction::Up { profile }) => forward_up(&profile)?,
+            None => port_forward(&target.unwrap_or_default(), &ports, namespace, auto)?,
+        },
+        Commands::Shell {
             pod,
-            local_port,
-            pod_port,
             namespace,
-        } => port_forward(&pod, local_port, pod_port, namespace)?,
-        Commands::Shell { pod, namespace } => shell_pod(&pod, namespace)?,
-        Commands::Debug { image, namespace } => debug_pod(&image, &namespace)?,
-        Commands::Restart { pod, namespace } => restart_pod(&pod, namespace)?,
-        Commands::Events { pod, namespace } => show_events(&pod, namespace)?,
-        Commands::Watch {
+            container,
+            shells,
+            rc,
+            record,
+        } => shell_pod(&pod, namespace, container.as_deref(), &shells, rc, record)?,
+        Commands::Debug {
+            action:
+                Some(DebugAction::Gc {
+                    older_than,
+                    dry_run,
+                    quiet,
+                }),
+            ..
+        } => gc_debug_pods(older_than.as_deref(), dry_run, quiet)?,
+        Commands::Debug {
+            action: None,
+            pod,
+            image,
             namespace,
-            interval,
-        } => watch_pods(namespace, interval)?,
-        Commands::Ctx { context } => switch_context(context)?,
+            target,
+            copy,
+            node,
+            profile,
+            dry_run,
+            record,
+            command,
+        } => debug_pod(
+            pod.as_deref(),
+            &DebugOptions {
+                image,
+                namespace,
+                target,
+                copy,
+                node,
+                profile,
+                dry_run,
+                command,
+                record,
+            },
+        )?,
+        Commands::Restart {
+            pod,
+            namespace,
+            all,
+            selector,
+            parallel,
+            rollout,
+            dry_run,
+            yes,
+            timeout,
+        } => {
+            let opts = RestartOptions {
+                rollout,
+                dry_run,
+                yes,
+                timeout,
+            };
+            if all {
+                let code = restart_all(&pod, namespace, selector.as_deref(), parallel, &opts)?;
+                std::process::exit(code);
+            }
+            restart_pod(&pod, namespace, &opts)?
+        }
+        Commands::Events {
+            pod,
+            namespace,
+            event_type,
+            kind,
+            reason,
+            since,
+            aggregate,
+            watch,
+        } => show_events(
+            pod.as_deref(),
+            namespace,
+            &EventFilter {
+                event_type,
+                kind,
+                reason,
+                since,
+            },
+            aggregate,
+            watch,
+        )?,
+        Commands::Timeline {
+            target,
+            namespace,
+            since,
+            grep,
+            json,
+        } => show_timeline(&target, namespace, since.as_deref(), grep.as_deref(), json)?,
+        Commands::Watch { namespace, .. } => watch_pods(namespace)?,
+        Commands::Ctx {
+            context,
+            shell,
+            export,
+        } => match context {
+            Some(context) if shell => context_shell(&context, export)?,
+            context => switch_context(context)?,
+        },
+        Commands::Ns { namespace } => switch_namespace(namespace)?,
+        Commands::Sessions { action } => match action {
+            SessionsAction::List => list_sessions()?,
+            SessionsAction::Replay {
+                id,
+                speed,
+                max_idle,
+            } => replay_session(&id, speed, max_idle)?,
+        },
         Commands::Plugin { name, args } => run_plugin(&name, &args)?,
     }
 
     Ok(())
 }
+
+fn positive_number(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(n) if n.is_finite() && n > 0.0 => Ok(n),
+        _ => Err(format!("'{}' is not a number greater than 0", text)),
+    }
+}
+
+fn non_negative_number(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(n) if n.is_finite() && n >= 0.0 => Ok(n),
+        _ => Err(format!("'{}' is not a number of 0 or more", text)),
+    }
+}
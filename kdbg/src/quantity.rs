@@ -0,0 +1,146 @@
+/// Parse a Kubernetes resource quantity ("250m", "1.5", "128Mi", "2G",
+/// "1e3") into its value in base units (cores or bytes)
+pub fn parse_quantity(quantity: &str) -> Option<f64> {
+    let quantity = quantity.trim();
+    let split = quantity
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(quantity.len());
+    let (number, suffix) = quantity.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier = match suffix {
+        "" => 1.0,
+        "n" => 1e-9,
+        "u" => 1e-6,
+        "m" => 1e-3,
+        "k" => 1e3,
+        "M" => 1e6,
+        "G" => 1e9,
+        "T" => 1e12,
+        "P" => 1e15,
+        "E" => 1e18,
+        "Ki" => 1024.0,
+        "Mi" => 1024.0_f64.powi(2),
+        "Gi" => 1024.0_f64.powi(3),
+        "Ti" => 1024.0_f64.powi(4),
+        "Pi" => 1024.0_f64.powi(5),
+        "Ei" => 1024.0_f64.powi(6),
+        // Decimal exponent form, e.g. "1e3" or "5E-2"
+        _ if suffix.starts_with(['e', 'E']) => 10f64.powi(suffix[1..].parse().ok()?),
+        _ => return None,
+    };
+
+    Some(number * multiplier)
+}
+
+/// CPU quantity in millicores
+pub fn parse_cpu_millis(quantity: &str) -> Option<f64> {
+    parse_quantity(quantity).map(|cores| cores * 1000.0)
+}
+
+/// Memory quantity in bytes
+pub fn parse_memory_bytes(quantity: &str) -> Option<f64> {
+    parse_quantity(quantity)
+}
+
+/// Format millicores the way kubectl does ("250m", "2")
+pub fn format_cpu(millis: f64) -> String {
+    if millis >= 1000.0 && (millis % 1000.0).abs() < f64::EPSILON {
+        format!("{}", (millis / 1000.0) as u64)
+    } else {
+        format!("{}m", millis.round() as u64)
+    }
+}
+
+/// Format bytes with binary units ("512Mi", "1.5Gi")
+pub fn format_memory(bytes: f64) -> String {
+    const UNITS: [&str; 5] = ["", "Ki", "Mi", "Gi", "Ti"];
+    let mut value = bytes;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 || value >= 100.0 || (value.fract()).abs() < 0.05 {
+        format!("{:.0}{}", value, UNITS[unit])
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("quantity should parse");
+        assert!(
+            (actual - expected).abs() <= expected.abs() * 1e-12,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn suffixes() {
+        assert_close(parse_quantity("250m"), 0.25);
+        assert_close(parse_quantity("2"), 2.0);
+        assert_close(parse_quantity("1k"), 1000.0);
+        assert_close(parse_quantity("3M"), 3e6);
+        assert_close(parse_quantity("2G"), 2e9);
+        assert_close(parse_quantity("1Ki"), 1024.0);
+        assert_close(parse_quantity("128Mi"), 128.0 * 1024.0 * 1024.0);
+        assert_close(parse_quantity("4Gi"), 4.0 * 1024f64.powi(3));
+        assert_close(parse_quantity("1Ti"), 1024f64.powi(4));
+        assert_close(parse_quantity("100n"), 1e-7);
+    }
+
+    #[test]
+    fn decimals_and_exponents() {
+        assert_close(parse_quantity("1.5"), 1.5);
+        assert_close(parse_quantity("0.5Gi"), 512.0 * 1024.0 * 1024.0);
+        assert_close(parse_quantity(" 1.25Mi "), 1.25 * 1024.0 * 1024.0);
+        assert_close(parse_quantity("1e3"), 1000.0);
+        assert_close(parse_quantity("5E-2"), 0.05);
+        assert_close(parse_quantity("+2"), 2.0);
+    }
+
+    #[test]
+    fn invalid_input() {
+        for quantity in [
+            "", "  ", "m", "abc", "1.2.3", "12Mix", "5mi", "1e", "1e1.5", "-",
+        ] {
+            assert_eq!(parse_quantity(quantity), None, "{:?}", quantity);
+        }
+    }
+
+    #[test]
+    fn cpu_and_memory_helpers() {
+        assert_close(parse_cpu_millis("1.5"), 1500.0);
+        assert_close(parse_cpu_millis("250m"), 250.0);
+        assert_close(parse_memory_bytes("1Ki"), 1024.0);
+        assert_eq!(parse_cpu_millis("x"), None);
+    }
+
+    #[test]
+    fn cpu_formatting() {
+        assert_eq!(format_cpu(250.0), "250m");
+        assert_eq!(format_cpu(1000.0), "1");
+        assert_eq!(format_cpu(2000.0), "2");
+        assert_eq!(format_cpu(1500.0), "1500m");
+        assert_eq!(format_cpu(0.4), "0m");
+    }
+
+    #[test]
+    fn memory_formatting() {
+        assert_eq!(format_memory(0.0), "0");
+        assert_eq!(format_memory(1023.0), "1023");
+        assert_eq!(format_memory(1024.0), "1Ki");
+        assert_eq!(format_memory(512.0 * 1024.0 * 1024.0), "512Mi");
+        assert_eq!(format_memory(1.5 * 1024f64.powi(3)), "1.5Gi");
+        assert_eq!(format_memory(2.0 * 1024f64.powi(4)), "2Ti");
+        assert_eq!(format_memory(2048.0 * 1024f64.powi(4)), "2048Ti");
+    }
+}
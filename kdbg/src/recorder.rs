@@ -0,0 +1,306 @@
+use crate::utils::get_kdbg_dir;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Who and what a recorded session is about (stored in the cast header)
+pub struct SessionMeta {
+    pub kind: String,
+    pub pod: String,
+    pub namespace: String,
+    pub container: Option<String>,
+}
+
+/// Get the directory recordings are written to (~/.kdbg/sessions)
+pub fn get_sessions_dir() -> PathBuf {
+    get_kdbg_dir().join("sessions")
+}
+
+/// Run an interactive command inside a PTY, recording everything it prints
+/// to an asciicast v2 file. Returns the command's exit status.
+pub fn run_recorded(mut cmd: Command, meta: &SessionMeta) -> Result<ExitStatus> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        anyhow::bail!("--record needs an interactive terminal");
+    }
+
+    let dir = get_sessions_dir();
+    fs::create_dir_all(&dir)?;
+    let started_at = chrono::Local::now();
+    let path = dir.join(format!(
+        "{}-{}-{}.cast",
+        started_at.format("%Y%m%d-%H%M%S"),
+        meta.pod,
+        std::process::id()
+    ));
+
+    let (cols, rows) = terminal_size();
+    let (master, slave) = open_pty(cols, rows)?;
+
+    let header = json!({
+        "version": 2,
+        "width": cols,
+        "height": rows,
+        "timestamp": started_at.timestamp(),
+        "title": format!("kdbg {} {}", meta.kind, meta.pod),
+        "env": {
+            "SHELL": std::env::var("SHELL").ok(),
+            "TERM": std::env::var("TERM").ok(),
+        },
+        "kdbg": {
+            "kind": meta.kind,
+            "context": current_context(),
+            "namespace": meta.namespace,
+            "pod": meta.pod,
+            "container": meta.container,
+            "user": std::env::var("USER").ok(),
+        },
+    });
+    let cast = Arc::new(Mutex::new(CastWriter::create(&path, &header)?));
+
+    cmd.stdin(Stdio::from(slave.try_clone()?))
+        .stdout(Stdio::from(slave.try_clone()?))
+        .stderr(Stdio::from(slave));
+    // Make the PTY the controlling terminal so kubectl sees a real TTY
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setsid() == -1 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let mut child = cmd.spawn()?;
+    // Drop our copies of the slave so reads on the master end at exit
+    drop(cmd);
+
+    let raw_mode = RawMode::enable()?;
+
+    // Keystrokes go straight to the PTY until the session ends
+    let stop = Arc::new(AtomicBool::new(false));
+    let input = master.try_clone()?;
+    let input_stop = Arc::clone(&stop);
+    let input_thread = thread::spawn(move || forward_input(input, &input_stop));
+
+    // Follow terminal resizes and record them as "r" events
+    let resize_fd = master.try_clone()?;
+    let resize_cast = Arc::clone(&cast);
+    let resize_stop = Arc::clone(&stop);
+    thread::spawn(move || {
+        let mut size = (cols, rows);
+        while !resize_stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(250));
+            let current = terminal_size();
+            if current != size {
+                size = current;
+                set_pty_size(&resize_fd, size.0, size.1);
+                let event = format!("{}x{}", size.0, size.1);
+                if resize_cast.lock().unwrap().event("r", &event).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut output = master;
+    let mut stdout = io::stdout();
+    let mut buf = [0u8; 8192];
+    let mut pending = Vec::new();
+    loop {
+        // Linux returns EIO and macOS returns 0 once the child side closes
+        let n = match output.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        stdout.write_all(&buf[..n])?;
+        stdout.flush()?;
+
+        pending.extend_from_slice(&buf[..n]);
+        let text = take_utf8(&mut pending);
+        if !text.is_empty() {
+            cast.lock().unwrap().event("o", &text)?;
+        }
+    }
+
+    let status = child.wait()?;
+    stop.store(true, Ordering::Relaxed);
+    let _ = input_thread.join();
+    drop(raw_mode);
+    cast.lock().unwrap().flush()?;
+
+    eprintln!("\r\n[kdbg] Session recorded to {}", path.display());
+
+    Ok(status)
+}
+
+/// Copy keystrokes to the PTY until `stop` is set. Polls instead of
+/// blocking in read, so nothing typed after the session is swallowed.
+fn forward_input(mut pty: File, stop: &AtomicBool) {
+    let fd = io::stdin().as_raw_fd();
+    let mut buf = [0u8; 1024];
+
+    while !stop.load(Ordering::Relaxed) {
+        let mut poll = libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        match unsafe { libc::poll(&mut poll, 1, 100) } {
+            0 => continue,
+            n if n < 0 => {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                break;
+            }
+            _ => {}
+        }
+        // Read the fd directly: io::Stdin buffers, which would hide input
+        // from the next poll
+        let n = unsafe { libc::read(fd, buf.as_mut_ptr().cast(), buf.len()) };
+        if n <= 0 || pty.write_all(&buf[..n as usize]).is_err() {
+            break;
+        }
+    }
+}
+
+struct CastWriter {
+    out: BufWriter<File>,
+    started: Instant,
+}
+
+impl CastWriter {
+    fn create(path: &PathBuf, header: &Value) -> Result<Self> {
+        // Casts hold whole terminal sessions, passwords typed included
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)?;
+        let mut out = BufWriter::new(file);
+        writeln!(out, "{}", header)?;
+        Ok(Self {
+            out,
+            started: Instant::now(),
+        })
+    }
+
+    fn event(&mut self, kind: &str, data: &str) -> io::Result<()> {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        writeln!(self.out, "{}", json!([elapsed, kind, data]))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Take the longest valid UTF-8 prefix, keeping a split multi-byte
+/// character for the next read
+fn take_utf8(pending: &mut Vec<u8>) -> String {
+    let valid = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        // Genuinely invalid bytes: emit everything lossily
+        Err(_) => pending.len(),
+    };
+    let text = String::from_utf8_lossy(&pending[..valid]).into_owned();
+    pending.drain(..valid);
+    text
+}
+
+/// Puts stdin into raw mode and restores the previous settings on drop
+struct RawMode {
+    original: libc::termios,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<Self> {
+        let fd = io::stdin().as_raw_fd();
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(fd, &mut original) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(fd, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self { original })
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        unsafe {
+            libc::tcsetattr(io::stdin().as_raw_fd(), libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+fn open_pty(cols: u16, rows: u16) -> io::Result<(File, File)> {
+    let mut master = 0;
+    let mut slave = 0;
+    let mut size = winsize(cols, rows);
+    let rc = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut::<libc::termios>(),
+            // Linux takes *const, macOS *mut
+            std::ptr::addr_of_mut!(size),
+        )
+    };
+    if rc != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    unsafe { Ok((File::from_raw_fd(master), File::from_raw_fd(slave))) }
+}
+
+fn terminal_size() -> (u16, u16) {
+    let mut size = winsize(0, 0);
+    let rc = unsafe { libc::ioctl(io::stdout().as_raw_fd(), libc::TIOCGWINSZ, &mut size) };
+    if rc != 0 || size.ws_col == 0 {
+        return (80, 24);
+    }
+    (size.ws_col, size.ws_row)
+}
+
+fn set_pty_size(pty: &File, cols: u16, rows: u16) {
+    let size = winsize(cols, rows);
+    unsafe {
+        libc::ioctl(pty.as_raw_fd(), libc::TIOCSWINSZ, &size);
+    }
+}
+
+fn winsize(cols: u16, rows: u16) -> libc::winsize {
+    libc::winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    }
+}
+
+fn current_context() -> Option<String> {
+    let output = Command::new("kubectl")
+        .args(["config", "current-context"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,280 @@
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+/// Aggregated stats for one log template
+pub struct TemplateStats {
+    pub count: u64,
+    pub first_seen: Option<DateTime<Utc>>,
+    pub last_seen: Option<DateTime<Utc>>,
+    pub example: String,
+    pub pods: BTreeMap<String, u64>,
+}
+
+/// Groups log lines into templates with variable parts masked
+#[derive(Default)]
+pub struct LogSummary {
+    templates: HashMap<String, TemplateStats>,
+    total: u64,
+}
+
+impl LogSummary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a line from `kubectl logs --timestamps`; lines without a
+    /// timestamp prefix are recorded as seen now
+    pub fn add(&mut self, pod: &str, line: &str) {
+        let (timestamp, message) = split_timestamp(line);
+        if message.trim().is_empty() {
+            return;
+        }
+
+        let seen = timestamp.unwrap_or_else(Utc::now);
+        let stats = self
+            .templates
+            .entry(template(message))
+            .or_insert_with(|| TemplateStats {
+                count: 0,
+                first_seen: None,
+                last_seen: None,
+                example: message.to_string(),
+                pods: BTreeMap::new(),
+            });
+
+        stats.count += 1;
+        stats.first_seen = Some(stats.first_seen.map_or(seen, |t| t.min(seen)));
+        stats.last_seen = Some(stats.last_seen.map_or(seen, |t| t.max(seen)));
+        *stats.pods.entry(pod.to_string()).or_insert(0) += 1;
+        self.total += 1;
+    }
+
+    /// Templates ordered by count, most frequent first
+    pub fn top(&self, n: usize) -> Vec<(&String, &TemplateStats)> {
+        let mut sorted: Vec<_> = self.templates.iter().collect();
+        sorted.sort_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(b.0)));
+        sorted.truncate(n);
+        sorted
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn distinct(&self) -> usize {
+        self.templates.len()
+    }
+}
+
+/// Split the RFC3339 prefix added by `kubectl logs --timestamps`
+pub fn split_timestamp(line: &str) -> (Option<DateTime<Utc>>, &str) {
+    if let Some((first, rest)) = line.split_once(' ') {
+        if let Ok(ts) = DateTime::parse_from_rfc3339(first) {
+            return (Some(ts.with_timezone(&Utc)), rest);
+        }
+    }
+    (None, line)
+}
+
+/// Mask the variable parts of a log line (numbers, IDs, IPs, UUIDs)
+///
+/// "GET /users/42 from 10.0.0.7 took 13ms" -> "GET /users/<num> from <ip> took <num>ms"
+pub fn template(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut word = String::new();
+
+    for c in line.chars() {
+        if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':') {
+            word.push(c);
+        } else {
+            out.push_str(&mask_word(&word));
+            word.clear();
+            out.push(c);
+        }
+    }
+    out.push_str(&mask_word(&word));
+
+    out
+}
+
+fn mask_word(word: &str) -> String {
+    // Keep sentence punctuation ("failed:", "done.") out of the match
+    let core = word.trim_end_matches(['.', ':']);
+    let suffix = &word[core.len()..];
+
+    if is_uuid(core) {
+        return format!("<uuid>{}", suffix);
+    }
+    if is_ip(core) {
+        return format!("<ip>{}", suffix);
+    }
+
+    let mut out = String::with_capacity(word.len());
+    let mut segment = String::new();
+    for c in core.chars() {
+        if matches!(c, '-' | '_' | '.' | ':') {
+            out.push_str(&mask_segment(&segment));
+            segment.clear();
+            out.push(c);
+        } else {
+            segment.push(c);
+        }
+    }
+    out.push_str(&mask_segment(&segment));
+    out.push_str(suffix);
+
+    out
+}
+
+fn mask_segment(segment: &str) -> String {
+    let digits = segment.chars().filter(|c| c.is_ascii_digit()).count();
+    if digits == 0 {
+        return segment.to_string();
+    }
+    if digits == segment.len() {
+        return "<num>".to_string();
+    }
+
+    // "500ms", "128Mi": a number with a unit suffix
+    let unit = segment.trim_start_matches(|c: char| c.is_ascii_digit());
+    if unit.len() < segment.len() && unit.chars().all(|c| c.is_ascii_alphabetic()) {
+        return format!("<num>{}", unit);
+    }
+
+    // Generated names and hashes like "7d9f8b6c5" or "x2k4z"
+    if segment.len() >= 5 && digits >= 2 {
+        return "<id>".to_string();
+    }
+
+    let mut out = String::new();
+    let mut in_digits = false;
+    for c in segment.chars() {
+        if c.is_ascii_digit() {
+            if !in_digits {
+                out.push_str("<num>");
+            }
+            in_digits = true;
+        } else {
+            out.push(c);
+            in_digits = false;
+        }
+    }
+    out
+}
+
+fn is_uuid(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    parts.len() == 5
+        && parts
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(p, len)| p.len() == len && p.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn is_ip(s: &str) -> bool {
+    // IPv4 with an optional port
+    let host = match s.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) && host.contains('.') => {
+            host
+        }
+        _ => s,
+    };
+    let octets: Vec<&str> = host.split('.').collect();
+    if octets.len() == 4
+        && octets
+            .iter()
+            .all(|o| !o.is_empty() && o.len() <= 3 && o.chars().all(|c| c.is_ascii_digit()))
+    {
+        return true;
+    }
+
+    // IPv6: compressed ("fe80::1") or full form, but not a "12:30:01" clock time
+    let groups: Vec<&str> = s.split(':').collect();
+    (s.contains("::") || groups.len() >= 6)
+        && s.chars().any(|c| c.is_ascii_digit())
+        && groups
+            .iter()
+            .all(|g| g.len() <= 4 && g.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_are_masked() {
+        assert_eq!(
+            template("GET /users/42 from 10.0.0.7 took 13ms"),
+            "GET /users/<num> from <ip> took <num>ms"
+        );
+        assert_eq!(template("retry 3 of 5"), "retry <num> of <num>");
+        assert_eq!(template("limit 128Mi reached."), "limit <num>Mi reached.");
+        assert_eq!(template("v2 api"), "v<num> api");
+    }
+
+    #[test]
+    fn hex_ids_are_masked() {
+        assert_eq!(
+            template("pod api-7d9f8b6c5-x2k4z ready"),
+            "pod api-<id>-<id> ready"
+        );
+        assert_eq!(template("commit 3fa85f6 pushed"), "commit <id> pushed");
+    }
+
+    #[test]
+    fn ips_are_masked() {
+        assert_eq!(
+            template("dial 10.0.0.7:8080: refused"),
+            "dial <ip>: refused"
+        );
+        assert_eq!(template("peer fe80::1 up"), "peer <ip> up");
+        assert_eq!(template("addr 2001:db8:0:0:0:0:2:1 set"), "addr <ip> set");
+        // A clock time is not an address
+        assert_eq!(template("at 12:30:01"), "at <num>:<num>:<num>");
+        assert!(is_ip("192.168.1.1"));
+        assert!(!is_ip("1.2.3"));
+        assert!(!is_ip("1.2.3.4444"));
+    }
+
+    #[test]
+    fn uuids_are_masked() {
+        assert_eq!(
+            template("request 3fa85f64-5717-4562-b3fc-2c963f66afa6 done."),
+            "request <uuid> done."
+        );
+        assert!(is_uuid("3FA85F64-5717-4562-B3FC-2C963F66AFA6"));
+        assert!(!is_uuid("3fa85f64-5717-4562-b3fc"));
+        assert!(!is_uuid("zzzzzzzz-5717-4562-b3fc-2c963f66afa6"));
+    }
+
+    #[test]
+    fn quoted_values_are_masked_inside_the_quotes() {
+        assert_eq!(
+            template(r#"user_id="12345" ip='10.1.2.3' name="bob""#),
+            r#"user_id="<num>" ip='<ip>' name="bob""#
+        );
+        assert_eq!(
+            template(r#"{"trace":"a1b2c3d4e5","ms":17}"#),
+            r#"{"trace":"<id>","ms":<num>}"#
+        );
+    }
+
+    #[test]
+    fn lines_differing_only_in_values_share_a_template() {
+        let mut summary = LogSummary::new();
+        summary.add("api-1", "GET /users/42 from 10.0.0.7 took 13ms");
+        summary.add("api-2", "GET /users/7 from 10.0.0.9 took 250ms");
+        assert_eq!(summary.distinct(), 1);
+        assert_eq!(summary.total(), 2);
+    }
+
+    #[test]
+    fn lines_differing_in_words_keep_separate_templates() {
+        let mut summary = LogSummary::new();
+        summary.add("api-1", "GET /users/42 took 13ms");
+        summary.add("api-1", "POST /users/42 took 13ms");
+        summary.add("api-1", "connection refused");
+        summary.add("api-1", "connection reset");
+        assert_eq!(summary.distinct(), 4);
+    }
+}
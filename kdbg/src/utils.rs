@@ -1,4 +1,24 @@
 use chrono::{DateTime, Utc};
+use colored::{Color, Colorize};
+
+/// Colors used to tell pods apart in multi-pod output
+const POD_COLORS: [Color; 10] = [
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::BrightGreen,
+    Color::BrightYellow,
+    Color::BrightBlue,
+    Color::BrightMagenta,
+    Color::BrightCyan,
+];
+
+/// Color for the i-th pod in multi-pod output
+pub fn pod_color(index: usize) -> Color {
+    POD_COLORS[index % POD_COLORS.len()]
+}
 
 /// Calculate age from timestamp (e.g., "2m", "5h", "3d")
 pub fn calculate_age(timestamp: &str) -> String {
@@ -11,7 +31,11 @@ pub fn calculate_age(timestamp: &str) -> String {
     let now = Utc::now();
     let duration = now.signed_duration_since(created);
 
-    let seconds = duration.num_seconds();
+    format_duration(duration.num_seconds())
+}
+
+/// Format a number of seconds the way ages are shown ("45s", "2m", "5h", "3d")
+pub fn format_duration(seconds: i64) -> String {
     if seconds < 60 {
         format!("{}s", seconds)
     } else if seconds < 3600 {
@@ -23,8 +47,106 @@ pub fn calculate_age(timestamp: &str) -> String {
     }
 }
 
+/// Parse a duration like "90s", "30m", "2h" or "7d" (plain numbers are seconds).
+/// Durations too long to count in i64 seconds are rejected.
+pub fn parse_duration(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number.parse().ok()?;
+
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return None,
+    };
+
+    number
+        .checked_mul(multiplier)
+        .filter(|seconds| i64::try_from(*seconds).is_ok())
+}
+
+/// Get kdbg state directory path (~/.kdbg)
+pub fn get_kdbg_dir() -> std::path::PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    std::path::PathBuf::from(home).join(".kdbg")
+}
+
 /// Get plugin directory path
 pub fn get_plugin_dir() -> std::path::PathBuf {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    std::path::PathBuf::from(home).join(".kdbg").join("plugins")
+    get_kdbg_dir().join("plugins")
+}
+
+/// Ask a yes/no question on the terminal. Anything but "y"/"yes" — including
+/// no terminal to ask on — counts as no.
+pub fn confirm(question: &str) -> bool {
+    use std::io::{IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+
+    print!("{} {} [y/N] ", "[CONFIRM]".yellow(), question);
+    let _ = std::io::stdout().flush();
+
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Let the user pick one of `items` by number on the terminal. Returns the
+/// index picked, or None when there is no terminal or nothing valid was
+/// entered.
+pub fn pick(prompt: &str, items: &[String], current: Option<&str>) -> Option<usize> {
+    use std::io::{IsTerminal, Write};
+
+    if items.is_empty() || !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return None;
+    }
+
+    for (i, item) in items.iter().enumerate() {
+        if Some(item.as_str()) == current {
+            println!("  {:>3}) {}", i + 1, item.green().bold());
+        } else {
+            println!("  {:>3}) {}", i + 1, item);
+        }
+    }
+    print!("{} {} [1-{}] ", "[SELECT]".yellow(), prompt, items.len());
+    let _ = std::io::stdout().flush();
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).ok()?;
+    let choice: usize = answer.trim().parse().ok()?;
+    (1..=items.len()).contains(&choice).then(|| choice - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("90"), Some(90));
+        assert_eq!(parse_duration("90s"), Some(90));
+        assert_eq!(parse_duration("30m"), Some(1800));
+        assert_eq!(parse_duration(" 2h "), Some(7200));
+        assert_eq!(parse_duration("7d"), Some(604800));
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("5w"), None);
+        assert_eq!(parse_duration("-5s"), None);
+    }
+
+    #[test]
+    fn overlong_durations_are_rejected() {
+        assert_eq!(parse_duration("99999999999999999d"), None);
+        assert_eq!(parse_duration("99999999999999999999"), None);
+        assert_eq!(parse_duration("9223372036854775808"), None);
+        assert_eq!(parse_duration("9223372036854775807"), Some(i64::MAX as u64));
+    }
 }